@@ -1,13 +1,20 @@
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 use bevy_rapier2d::prelude::*;
 
-const FLOOR_RISE: f32 = 50.;
+use crate::camera::{camera_movement_system, camera_zoom_system};
+use crate::platform::{Platform, PlatformSurface};
+
+pub const FLOOR_RISE: f32 = 50.;
 const WALL_WIDTH: f32 = 50.;
 
 const ARENA_COLOR: Color = Color::rgb(0.29, 0.0, 0.51);
 
 pub struct ArenaPlugin;
 
+/// The ground every run starts on, a platform that is never recycled
+#[derive(Component)]
+pub struct Floor;
+
 fn setup_floor(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -29,6 +36,10 @@ fn setup_floor(
             ..default()
         },
         Collider::cuboid(width / 2., height / 2.),
+        // The platform generation starts from the floor, so the giraffe has to stand on it
+        Platform,
+        PlatformSurface::Normal,
+        Floor,
     ));
 }
 
@@ -78,11 +89,17 @@ fn setup_walls(
 
 fn auto_move_walls(
     mut wall_query: Query<&mut Transform, (With<Wall>, Without<WallMoveTarget>)>,
-    target_query: Query<&Transform, (With<WallMoveTarget>, Without<Wall>)>,
+    target_query: Query<
+        (&Transform, Option<&OrthographicProjection>),
+        (With<WallMoveTarget>, Without<Wall>),
+    >,
 ) {
-    let target_avg_y = target_query.single().translation.y;
-    for mut wall in wall_query.iter_mut() {
-        wall.translation.y = target_avg_y;
+    if let Ok((target, projection)) = target_query.get_single() {
+        for mut wall in wall_query.iter_mut() {
+            wall.translation.y = target.translation.y;
+            // Stretch with the zoom so the walls always reach the top and bottom of the view
+            wall.scale.y = projection.map_or(1.0, |projection| projection.scale);
+        }
     }
 }
 
@@ -135,6 +152,11 @@ impl Plugin for ArenaPlugin {
             .add_startup_system(setup_walls)
             //.add_startup_system(test_ball)
             .add_system(test_ball_movement)
-            .add_system(auto_move_walls);
+            // Walls follow the already smoothed camera of this frame
+            .add_system(
+                auto_move_walls
+                    .after(camera_movement_system)
+                    .after(camera_zoom_system),
+            );
     }
 }
@@ -1,13 +1,91 @@
 use bevy::prelude::*;
+use bevy_rapier2d::prelude::Velocity;
 
 use crate::arena::WallMoveTarget;
+use crate::game_state::GameState;
+use crate::locomotion::{Locomotion, LocomotionState};
+use crate::neck::NeckPoints;
 
 pub fn camera_viewport_to_world() {
     todo!()
 }
 
+/// How the camera follows its target
+#[derive(Resource)]
+pub struct CameraFollowSettings {
+    /// How quickly the camera catches up, higher is snappier
+    pub damping: f32,
+    /// How far the target can move vertically before the camera starts following
+    pub dead_zone: f32,
+    /// Seconds of the target's swing velocity the camera looks ahead by
+    pub look_ahead_time: f32,
+    pub max_look_ahead: f32,
+    /// How far below its highest point the camera may scroll back down
+    pub max_scroll_back: f32,
+}
+
+impl Default for CameraFollowSettings {
+    fn default() -> Self {
+        Self {
+            damping: 5.0,
+            dead_zone: 100.0,
+            look_ahead_time: 0.3,
+            max_look_ahead: 200.0,
+            max_scroll_back: 300.0,
+        }
+    }
+}
+
+/// How the camera zooms out to keep the giraffe and its neck in view
+#[derive(Resource)]
+pub struct CameraZoomSettings {
+    /// Orthographic scale when grounded, 1 is one pixel per world unit
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// Space kept between the framed points and the edge of the view, in world units
+    pub margin: f32,
+    /// Extra scale per pixel per second of speed while in the air
+    pub speed_zoom: f32,
+    /// How quickly the zoom catches up, higher is snappier
+    pub damping: f32,
+}
+
+impl Default for CameraZoomSettings {
+    fn default() -> Self {
+        Self {
+            min_scale: 1.0,
+            max_scale: 2.5,
+            margin: 150.0,
+            speed_zoom: 0.0005,
+            damping: 3.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct CameraFollow {
+    /// Highest the camera got during the run
+    max_y: f32,
+    /// Jump straight to the target instead of moving smoothly, used when a run starts
+    snap: bool,
+}
+
+impl Default for CameraFollow {
+    fn default() -> Self {
+        Self {
+            max_y: f32::NEG_INFINITY,
+            snap: true,
+        }
+    }
+}
+
 fn setup_camera(mut commands: Commands) {
-    commands.spawn((Camera2dBundle::default(), WallMoveTarget, MainCamera));
+    commands.spawn((
+        Camera2dBundle::default(),
+        WallMoveTarget,
+        MainCamera,
+        CameraFollow::default(),
+    ));
 }
 
 #[derive(Component)]
@@ -16,14 +94,102 @@ pub struct MainCamera;
 #[derive(Component)]
 pub struct CameraTarget;
 
-fn camera_movement_system(
-    mut camera_query: Query<&mut Transform, (With<Camera>, Without<CameraTarget>)>,
-    target_query: Query<&Transform, With<CameraTarget>>,
+/// Moves the camera back down to where the giraffe spawns, so the new run doesn't start at the
+/// height the last one ended at
+fn reset_camera_follow(
+    mut query: Query<(&mut Transform, &mut GlobalTransform, &mut CameraFollow)>,
+) {
+    for (mut transform, mut global, mut follow) in query.iter_mut() {
+        transform.translation.y = 0.0;
+        // Platform generation reads the global transform before it's propagated again
+        *global = GlobalTransform::from(*transform);
+        *follow = CameraFollow {
+            max_y: 0.0,
+            ..default()
+        };
+    }
+}
+
+pub fn camera_movement_system(
+    mut camera_query: Query<(&mut Transform, &mut CameraFollow), Without<CameraTarget>>,
+    target_query: Query<(&Transform, Option<&Velocity>, Option<&Locomotion>), With<CameraTarget>>,
+    settings: Res<CameraFollowSettings>,
+    time: Res<Time>,
+) {
+    if let Ok((target, velocity, locomotion)) = target_query.get_single() {
+        let swinging = locomotion.map_or(false, |l| l.state() == LocomotionState::Swinging);
+        let look_ahead = match velocity {
+            Some(velocity) if swinging => (velocity.linvel.y * settings.look_ahead_time)
+                .clamp(-settings.max_look_ahead, settings.max_look_ahead),
+            _ => 0.0,
+        };
+        let desired = target.translation.y + look_ahead;
+
+        for (mut camera_trans, mut follow) in camera_query.iter_mut() {
+            if follow.snap {
+                follow.snap = false;
+                follow.max_y = desired;
+                camera_trans.translation.y = desired;
+                continue;
+            }
+
+            let y = camera_trans.translation.y;
+            let goal = if desired > y + settings.dead_zone {
+                desired - settings.dead_zone
+            } else if desired < y - settings.dead_zone {
+                desired + settings.dead_zone
+            } else {
+                y
+            }
+            .max(follow.max_y - settings.max_scroll_back);
+
+            // Frame rate independent exponential smoothing
+            let t = 1.0 - (-settings.damping * time.delta_seconds()).exp();
+            camera_trans.translation.y = y + (goal - y) * t;
+            follow.max_y = follow.max_y.max(camera_trans.translation.y);
+        }
+    }
+}
+
+pub fn camera_zoom_system(
+    mut camera_query: Query<(&Transform, &mut OrthographicProjection), With<CameraFollow>>,
+    target_query: Query<(&Transform, Option<&Velocity>, Option<&Locomotion>), With<CameraTarget>>,
+    neck_query: Query<&NeckPoints>,
+    settings: Res<CameraZoomSettings>,
+    windows: Res<Windows>,
+    time: Res<Time>,
 ) {
-    let target_avg_y = target_query.single().translation.y;
+    let half_view = if let Some(window) = windows.get_primary() {
+        Vec2::new(window.width(), window.height()) / 2.0
+    } else {
+        return;
+    };
+
+    for (camera_trans, mut projection) in camera_query.iter_mut() {
+        let camera_pos = camera_trans.translation.truncate();
+        // Scale at which the point is still inside the view with the margin around it
+        let fit = |point: Vec2| {
+            let needed = ((point - camera_pos).abs() + settings.margin) / half_view;
+            needed.max_element()
+        };
+
+        let target_scale = match target_query.get_single() {
+            Ok((_, _, Some(locomotion))) if locomotion.state().is_grounded() => settings.min_scale,
+            Ok((target, velocity, _)) => {
+                let speed = velocity.map_or(0.0, |velocity| velocity.linvel.length());
+                let mut scale = fit(target.translation.truncate())
+                    .max(settings.min_scale + speed * settings.speed_zoom);
+                if let Ok(neck) = neck_query.get_single() {
+                    scale = scale.max(fit(neck.anchor()));
+                }
+                scale
+            }
+            Err(_) => settings.min_scale,
+        }
+        .clamp(settings.min_scale, settings.max_scale);
 
-    for mut camera_trans in camera_query.iter_mut() {        
-        camera_trans.translation.y = target_avg_y;
+        let t = 1.0 - (-settings.damping * time.delta_seconds()).exp();
+        projection.scale += (target_scale - projection.scale) * t;
     }
 }
 
@@ -31,7 +197,13 @@ pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_camera)
-            .add_system(camera_movement_system);
+        app.init_resource::<CameraFollowSettings>()
+            .init_resource::<CameraZoomSettings>()
+            .add_startup_system(setup_camera)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(reset_camera_follow),
+            )
+            .add_system(camera_movement_system)
+            .add_system(camera_zoom_system.after(camera_movement_system));
     }
 }
@@ -0,0 +1,316 @@
+use bevy::prelude::*;
+use bevy::render::camera::CameraUpdateSystem;
+use bevy::transform::TransformSystem;
+use bevy_rapier2d::prelude::*;
+
+use crate::camera::MainCamera;
+use crate::crumbling_platform::{CrumbleEvent, CrumbleStage};
+use crate::game_state::GameState;
+use crate::shooting_head::NeckAttachEvent;
+
+pub struct CameraEffectsPlugin;
+
+/// Screen feedback requested by gameplay, effects of the same kind add up
+#[derive(Clone, Copy, Debug)]
+pub enum CameraEffect {
+    /// Adds trauma, the shake grows with the square of the trauma
+    Shake { trauma: f32 },
+    /// Zooms in by a part of the view, e.g. 0.1 for 10%, and eases back out
+    ZoomPunch { amount: f32 },
+    /// Stops the physics for a moment
+    HitStop { seconds: f32 },
+}
+
+#[derive(Resource)]
+pub struct CameraEffectSettings {
+    /// Trauma lost per second
+    pub trauma_decay: f32,
+    /// Largest shake offset in pixels, at full trauma
+    pub max_shake_offset: f32,
+    /// Largest shake rotation in radians, at full trauma
+    pub max_shake_angle: f32,
+    /// How fast the shake wobbles
+    pub shake_frequency: f32,
+    /// Zoom punch lost per second
+    pub zoom_decay: f32,
+    pub max_zoom_punch: f32,
+    /// Longest a stacked hit-stop can last
+    pub max_hit_stop: f32,
+}
+
+impl Default for CameraEffectSettings {
+    fn default() -> Self {
+        Self {
+            trauma_decay: 1.5,
+            max_shake_offset: 30.0,
+            max_shake_angle: 0.05,
+            shake_frequency: 25.0,
+            zoom_decay: 0.5,
+            max_zoom_punch: 0.3,
+            max_hit_stop: 0.2,
+        }
+    }
+}
+
+/// Current state of the effects, only changes with the frame time so replays look the same
+#[derive(Resource, Default)]
+pub struct CameraEffects {
+    pub trauma: f32,
+    pub zoom_punch: f32,
+    pub hit_stop: f32,
+    /// Time the shake noise is sampled at
+    time: f32,
+    /// What was added to the camera this frame, removed again before gameplay runs
+    applied_offset: Vec2,
+    applied_angle: f32,
+    applied_zoom: f32,
+    frozen: bool,
+    /// Physics time scale from before the hit-stop, put back when it ends
+    time_scale: f32,
+}
+
+/// Smooth pseudo-random value in -1..1, the same for the same inputs
+fn shake_noise(time: f32, seed: f32) -> f32 {
+    ((time + seed).sin() + (time * 2.3 + seed * 1.7).sin() * 0.5) / 1.5
+}
+
+fn receive_camera_effects(
+    mut events: EventReader<CameraEffect>,
+    mut effects: ResMut<CameraEffects>,
+    settings: Res<CameraEffectSettings>,
+) {
+    for event in events.iter() {
+        match *event {
+            CameraEffect::Shake { trauma } => {
+                effects.trauma = (effects.trauma + trauma).clamp(0.0, 1.0);
+            }
+            CameraEffect::ZoomPunch { amount } => {
+                effects.zoom_punch =
+                    (effects.zoom_punch + amount).clamp(0.0, settings.max_zoom_punch);
+            }
+            CameraEffect::HitStop { seconds } => {
+                effects.hit_stop = (effects.hit_stop + seconds).clamp(0.0, settings.max_hit_stop);
+            }
+        }
+    }
+}
+
+fn feedback_from_gameplay(
+    mut attach_events: EventReader<NeckAttachEvent>,
+    mut crumble_events: EventReader<CrumbleEvent>,
+    mut effects: EventWriter<CameraEffect>,
+) {
+    for _ in attach_events.iter() {
+        effects.send(CameraEffect::ZoomPunch { amount: 0.05 });
+        effects.send(CameraEffect::Shake { trauma: 0.2 });
+    }
+    for event in crumble_events.iter() {
+        if event.stage == CrumbleStage::Broken {
+            effects.send(CameraEffect::Shake { trauma: 0.4 });
+        }
+    }
+}
+
+/// Takes the effects off the camera, so gameplay sees where the camera really is
+fn remove_camera_effects(
+    mut query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+    mut effects: ResMut<CameraEffects>,
+) {
+    if let Ok((mut transform, mut projection)) = query.get_single_mut() {
+        transform.translation -= effects.applied_offset.extend(0.0);
+        transform.rotate_z(-effects.applied_angle);
+        if effects.applied_zoom > 0.0 {
+            projection.scale /= effects.applied_zoom;
+        }
+    }
+    effects.applied_offset = Vec2::ZERO;
+    effects.applied_angle = 0.0;
+    effects.applied_zoom = 0.0;
+}
+
+fn apply_camera_effects(
+    mut query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+    mut effects: ResMut<CameraEffects>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+    settings: Res<CameraEffectSettings>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    effects.time += dt * settings.shake_frequency;
+    effects.trauma = (effects.trauma - settings.trauma_decay * dt).max(0.0);
+    effects.zoom_punch = (effects.zoom_punch - settings.zoom_decay * dt).max(0.0);
+    effects.hit_stop = (effects.hit_stop - dt).max(0.0);
+
+    let frozen = effects.hit_stop > 0.0;
+    if frozen != effects.frozen {
+        effects.frozen = frozen;
+        if let TimestepMode::Variable { time_scale, .. } = &mut rapier_config.timestep_mode {
+            if frozen {
+                effects.time_scale = *time_scale;
+                *time_scale = 0.0;
+            } else {
+                *time_scale = effects.time_scale;
+            }
+        }
+    }
+
+    if let Ok((mut transform, mut projection)) = query.get_single_mut() {
+        let shake = effects.trauma * effects.trauma;
+        let t = effects.time;
+        let noise = Vec2::new(shake_noise(t, 0.0), shake_noise(t, 10.0));
+
+        effects.applied_offset = noise * settings.max_shake_offset * shake;
+        effects.applied_angle = shake_noise(t, 20.0) * settings.max_shake_angle * shake;
+        effects.applied_zoom = 1.0 - effects.zoom_punch;
+
+        transform.translation += effects.applied_offset.extend(0.0);
+        transform.rotate_z(effects.applied_angle);
+        projection.scale *= effects.applied_zoom;
+    }
+}
+
+fn reset_camera_effects(mut effects: ResMut<CameraEffects>) {
+    effects.trauma = 0.0;
+    effects.zoom_punch = 0.0;
+    effects.hit_stop = 0.0;
+}
+
+impl Plugin for CameraEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CameraEffect>()
+            .init_resource::<CameraEffectSettings>()
+            .init_resource::<CameraEffects>()
+            .add_system_to_stage(CoreStage::PreUpdate, remove_camera_effects)
+            .add_system(feedback_from_gameplay)
+            .add_system(receive_camera_effects.after(feedback_from_gameplay))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(reset_camera_effects),
+            )
+            // Applied after everything else moved the camera and before it gets rendered
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                apply_camera_effects
+                    .before(TransformSystem::TransformPropagate)
+                    .before(CameraUpdateSystem),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use bevy::ecs::event::Events;
+
+    use super::*;
+
+    const TIME_SCALE: f32 = 0.5;
+    const CAMERA_POS: Vec3 = Vec3::new(10.0, 20.0, 0.0);
+
+    fn test_app() -> App {
+        let mut time = Time::default();
+        time.update_with_instant(Instant::now());
+
+        let mut app = App::new();
+        app.add_state(GameState::Playing)
+            .add_event::<NeckAttachEvent>()
+            .add_event::<CrumbleEvent>()
+            .insert_resource(time)
+            .insert_resource(RapierConfiguration {
+                timestep_mode: TimestepMode::Variable {
+                    max_dt: 1.0 / 60.0,
+                    time_scale: TIME_SCALE,
+                    substeps: 1,
+                },
+                ..default()
+            })
+            .add_plugin(CameraEffectsPlugin);
+
+        app.world.spawn((
+            Transform::from_translation(CAMERA_POS),
+            OrthographicProjection::default(),
+            MainCamera,
+        ));
+        app.update();
+        app
+    }
+
+    fn step(app: &mut App, seconds: f32) {
+        let mut time = app.world.resource_mut::<Time>();
+        let last = time.last_update().unwrap();
+        time.update_with_instant(last + Duration::from_secs_f32(seconds));
+        app.update();
+    }
+
+    fn send(app: &mut App, effect: CameraEffect) {
+        app.world.resource_mut::<Events<CameraEffect>>().send(effect);
+    }
+
+    fn time_scale(app: &App) -> f32 {
+        match app.world.resource::<RapierConfiguration>().timestep_mode {
+            TimestepMode::Variable { time_scale, .. } => time_scale,
+            _ => unreachable!(),
+        }
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn effects_add_up_clamp_and_decay() {
+        let mut app = test_app();
+        let settings = CameraEffectSettings::default();
+
+        for _ in 0..2 {
+            send(&mut app, CameraEffect::Shake { trauma: 0.7 });
+            send(&mut app, CameraEffect::ZoomPunch { amount: 0.2 });
+            send(&mut app, CameraEffect::HitStop { seconds: 0.15 });
+        }
+        step(&mut app, 0.0);
+
+        let effects = app.world.resource::<CameraEffects>();
+        assert_close(effects.trauma, 1.0);
+        assert_close(effects.zoom_punch, settings.max_zoom_punch);
+        assert_close(effects.hit_stop, settings.max_hit_stop);
+        assert_close(time_scale(&app), 0.0);
+
+        step(&mut app, 0.1);
+
+        let effects = app.world.resource::<CameraEffects>();
+        assert_close(effects.trauma, 1.0 - settings.trauma_decay * 0.1);
+        assert_close(effects.zoom_punch, settings.max_zoom_punch - settings.zoom_decay * 0.1);
+        assert_close(effects.hit_stop, settings.max_hit_stop - 0.1);
+        assert_close(time_scale(&app), 0.0);
+
+        step(&mut app, 0.15);
+
+        let effects = app.world.resource::<CameraEffects>();
+        assert_close(effects.trauma, 1.0 - settings.trauma_decay * 0.25);
+        assert_close(effects.zoom_punch, settings.max_zoom_punch - settings.zoom_decay * 0.25);
+        assert_close(effects.hit_stop, 0.0);
+        assert_close(time_scale(&app), TIME_SCALE);
+    }
+
+    #[test]
+    fn removing_effects_undoes_applying_them() {
+        let mut app = test_app();
+
+        send(&mut app, CameraEffect::Shake { trauma: 0.8 });
+        send(&mut app, CameraEffect::ZoomPunch { amount: 0.2 });
+        for _ in 0..5 {
+            step(&mut app, 0.05);
+        }
+
+        let mut remove = SystemStage::single(remove_camera_effects);
+        remove.run(&mut app.world);
+
+        let (transform, projection) = app
+            .world
+            .query_filtered::<(&Transform, &OrthographicProjection), With<MainCamera>>()
+            .single(&app.world);
+        assert!(transform.translation.abs_diff_eq(CAMERA_POS, 1e-4));
+        assert!(transform.rotation.abs_diff_eq(Quat::IDENTITY, 1e-5));
+        assert_close(projection.scale, 1.0);
+    }
+}
@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::game_state::GameState;
+use crate::locomotion::{Locomotion, LocomotionState};
+use crate::neck::{NeckBendingPoints, NeckPoints};
+use crate::on_floor::OnFloor;
+
+/// How long a platform shakes before breaking
+const SHAKE_TIME: f32 = 1.0;
+/// Largest offset of the shaking sprite in pixels
+const SHAKE_AMOUNT: f32 = 4.0;
+/// Multiplied into the sprite's own color while the platform shakes
+const SHAKING_TINT: Color = Color::rgb(1.0, 0.6, 0.4);
+
+pub struct CrumblingPlatformPlugin;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrumbleStage {
+    Solid,
+    Shaking,
+    Broken,
+}
+
+/// Sent when a crumbling platform starts shaking and when it breaks
+pub struct CrumbleEvent {
+    pub platform: Entity,
+    pub stage: CrumbleStage,
+}
+
+#[derive(Component)]
+pub struct CrumblingPlatform {
+    stage: CrumbleStage,
+    timer: Timer,
+    /// Color of the sprite before the shaking tint, put back when it breaks
+    color: Color,
+}
+
+impl CrumblingPlatform {
+    pub fn new() -> Self {
+        Self {
+            stage: CrumbleStage::Solid,
+            timer: Timer::from_seconds(SHAKE_TIME, TimerMode::Once),
+            color: Color::WHITE,
+        }
+    }
+}
+
+fn tinted(color: Color, tint: Color) -> Color {
+    let [r, g, b, a] = color.as_rgba_f32();
+    let [tint_r, tint_g, tint_b, _] = tint.as_rgba_f32();
+    Color::rgba(r * tint_r, g * tint_g, b * tint_b, a)
+}
+
+impl Default for CrumblingPlatform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn crumble_platforms(
+    mut query: Query<(Entity, &mut CrumblingPlatform, &mut Sprite, &mut Visibility)>,
+    mut giraffe_query: Query<(&mut Locomotion, &OnFloor)>,
+    time: Res<Time>,
+    mut events: EventWriter<CrumbleEvent>,
+    mut commands: Commands,
+) {
+    for (entity, mut platform, mut sprite, mut visibility) in query.iter_mut() {
+        match platform.stage {
+            CrumbleStage::Solid => {
+                let landed = giraffe_query
+                    .iter()
+                    .any(|(_, on_floor)| on_floor.on_which_floor == entity);
+                if landed {
+                    platform.stage = CrumbleStage::Shaking;
+                    platform.color = sprite.color;
+                    sprite.color = tinted(sprite.color, SHAKING_TINT);
+                    events.send(CrumbleEvent {
+                        platform: entity,
+                        stage: CrumbleStage::Shaking,
+                    });
+                }
+            }
+            CrumbleStage::Shaking => {
+                // Jittering the anchor shakes only the sprite, the collider stays in place
+                let size = sprite.custom_size.unwrap_or(Vec2::ONE);
+                let mut rng = rand::thread_rng();
+                let offset = Vec2::new(
+                    rng.gen_range(-SHAKE_AMOUNT..=SHAKE_AMOUNT),
+                    rng.gen_range(-SHAKE_AMOUNT..=SHAKE_AMOUNT),
+                );
+                sprite.anchor = Anchor::Custom(offset / size);
+
+                if platform.timer.tick(time.delta()).finished() {
+                    platform.stage = CrumbleStage::Broken;
+                    sprite.color = platform.color;
+                    sprite.anchor = Anchor::Center;
+                    visibility.is_visible = false;
+                    commands
+                        .entity(entity)
+                        .remove::<(Collider, NeckBendingPoints)>();
+
+                    for (mut locomotion, on_floor) in giraffe_query.iter_mut() {
+                        if on_floor.on_which_floor == entity {
+                            locomotion.transition(LocomotionState::Airborne);
+                        }
+                    }
+
+                    events.send(CrumbleEvent {
+                        platform: entity,
+                        stage: CrumbleStage::Broken,
+                    });
+                }
+            }
+            CrumbleStage::Broken => {}
+        }
+    }
+}
+
+/// Lets go of a broken platform with the neck
+fn detach_crumbled_necks(
+    neck_query: Query<(Entity, &NeckPoints)>,
+    mut events: EventReader<CrumbleEvent>,
+    mut commands: Commands,
+) {
+    for event in events.iter() {
+        if event.stage != CrumbleStage::Broken {
+            continue;
+        }
+
+        for (entity, neck) in neck_query.iter() {
+            let held = neck
+                .holds
+                .iter()
+                .flatten()
+                .any(|hold| hold.entity == event.platform);
+            if held {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+impl Plugin for CrumblingPlatformPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CrumbleEvent>().add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(crumble_platforms)
+                .with_system(detach_crumbled_necks.after(crumble_platforms)),
+        );
+    }
+}
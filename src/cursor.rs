@@ -9,6 +9,7 @@ pub struct CursorWorldPos {
     pub pos: Result<Vec3, Vec3>,
 }
 
+/// Uses the camera as it was last rendered, so it's right at any zoom and with effects applied
 fn update_cursor_pos(
     camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
     windows: Res<Windows>,
@@ -35,6 +36,6 @@ impl Plugin for CursorWorldPosPlugin {
         app.insert_resource(CursorWorldPos {
             pos: Ok(Vec3::ZERO),
         })
-        .add_system(update_cursor_pos);
+        .add_system_to_stage(CoreStage::PreUpdate, update_cursor_pos);
     }
 }
@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::hazard::HazardSettings;
+use crate::platform::{PlatformKind, PlatformSurface};
+use crate::ron_asset::{updated_source, RonSource};
+use crate::PIXELS_PER_METER;
+
+/// Path in the asset folder
+const DIFFICULTY_FILE: &str = "difficulty.ron";
+
+pub struct DifficultyPlugin;
+
+/// Platform generation settings used from a given height up to the next band
+#[derive(Deserialize, Clone)]
+pub struct DifficultyBand {
+    /// Height in meters from which the band is active
+    pub from_height: f32,
+    pub platform_width: f32,
+    /// Vertical distance between platforms in pixels
+    pub spacing: f32,
+    /// Part of the arena width platforms are spread over
+    pub spread: f32,
+    /// Platform kinds with their relative weights
+    pub kinds: Vec<(PlatformKind, f32)>,
+    /// Platform surfaces with their relative weights, only normal ones when left out
+    #[serde(default)]
+    pub surfaces: Vec<(PlatformSurface, f32)>,
+    /// Speed the rising hazard climbs at in pixels per second
+    #[serde(default)]
+    pub hazard_speed: f32,
+}
+
+#[derive(Resource, Deserialize)]
+pub struct DifficultyProfile {
+    pub bands: Vec<DifficultyBand>,
+    /// There's no rising hazard when left out
+    #[serde(default)]
+    pub hazard: Option<HazardSettings>,
+}
+
+impl Default for DifficultyProfile {
+    fn default() -> Self {
+        Self {
+            bands: vec![DifficultyBand {
+                from_height: 0.0,
+                platform_width: 300.0,
+                spacing: 200.0,
+                spread: 1.0,
+                kinds: vec![(PlatformKind::Static, 1.0)],
+                surfaces: vec![(PlatformSurface::Normal, 1.0)],
+                hazard_speed: 0.0,
+            }],
+            hazard: None,
+        }
+    }
+}
+
+impl DifficultyProfile {
+    /// Reads a profile, refusing ones platforms can't be generated from
+    pub fn parse(data: &str) -> Result<Self, String> {
+        let profile = ron::from_str::<Self>(data).map_err(|err| err.to_string())?;
+
+        if profile.bands.is_empty() {
+            return Err("there are no bands".to_string());
+        }
+        if profile.bands.iter().any(|band| band.spacing <= 0.0) {
+            return Err("a band has no positive spacing".to_string());
+        }
+        Ok(profile)
+    }
+
+    /// Band active at the given height in pixels
+    pub fn band_at(&self, height: f32) -> &DifficultyBand {
+        let meters = height / PIXELS_PER_METER;
+        self.bands
+            .iter()
+            .filter(|band| band.from_height <= meters)
+            .max_by(|a, b| a.from_height.total_cmp(&b.from_height))
+            .unwrap_or(&self.bands[0])
+    }
+}
+
+#[derive(Resource)]
+struct DifficultyFile(Handle<RonSource>);
+
+impl FromWorld for DifficultyFile {
+    fn from_world(world: &mut World) -> Self {
+        DifficultyFile(world.resource::<AssetServer>().load(DIFFICULTY_FILE))
+    }
+}
+
+/// Replaces the profile whenever the data file is loaded or changes, a broken file is skipped
+fn update_difficulty(
+    mut events: EventReader<AssetEvent<RonSource>>,
+    file: Res<DifficultyFile>,
+    sources: Res<Assets<RonSource>>,
+    mut difficulty: ResMut<DifficultyProfile>,
+) {
+    if let Some(source) = updated_source(&mut events, &file.0, &sources) {
+        match DifficultyProfile::parse(source) {
+            Ok(profile) => *difficulty = profile,
+            Err(err) => warn!("Couldn't load {}: {}", DIFFICULTY_FILE, err),
+        }
+    }
+}
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DifficultyProfile>()
+            .init_resource::<DifficultyFile>()
+            .add_system(update_difficulty);
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::camera::MainCamera;
+use crate::giraffe::Giraffe;
+use crate::platform_spawner::MaxHeightComponent;
+
+pub struct GameStatePlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameState {
+    MainMenu,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+/// Marks everything that belongs to a single run and is removed on restart
+#[derive(Component)]
+pub struct RunEntity;
+
+#[derive(Resource)]
+pub struct GameRules {
+    /// How far below the camera the giraffe can fall before the run ends
+    pub fall_below_camera: f32,
+    /// How far below the highest reached point the giraffe can fall before the run ends
+    pub fall_below_max_height: f32,
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            fall_below_camera: crate::WINDOW_HEIGHT,
+            fall_below_max_height: 1500.0,
+        }
+    }
+}
+
+fn despawn_run_entities(query: Query<Entity, With<RunEntity>>, mut commands: Commands) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn set_physics_active(active: bool) -> impl FnMut(ResMut<RapierConfiguration>) {
+    move |mut config: ResMut<RapierConfiguration>| {
+        config.physics_pipeline_active = active;
+    }
+}
+
+fn check_game_over(
+    giraffe_query: Query<&Transform, With<Giraffe>>,
+    camera_query: Query<&Transform, With<MainCamera>>,
+    max_height_query: Query<&MaxHeightComponent>,
+    rules: Res<GameRules>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if let Ok(giraffe) = giraffe_query.get_single() {
+        let y = giraffe.translation.y;
+
+        let below_camera = camera_query
+            .get_single()
+            .map_or(false, |camera| y < camera.translation.y - rules.fall_below_camera);
+        let below_max_height = max_height_query
+            .get_single()
+            .map_or(false, |max| y < max.height - rules.fall_below_max_height);
+
+        if below_camera || below_max_height {
+            let _ = state.set(GameState::GameOver);
+        }
+    }
+}
+
+fn pause_input(mut keys: ResMut<Input<KeyCode>>, mut state: ResMut<State<GameState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        // Reset so the paused screen doesn't see the same press and resume right away
+        keys.reset(KeyCode::Escape);
+        let _ = state.push(GameState::Paused);
+    }
+}
+
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state(GameState::MainMenu)
+            .init_resource::<GameRules>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::MainMenu)
+                    .with_system(despawn_run_entities)
+                    .with_system(set_physics_active(false)),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(despawn_run_entities)
+                    .with_system(set_physics_active(true)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(check_game_over)
+                    .with_system(pause_input),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::Paused).with_system(set_physics_active(false)),
+            )
+            .add_system_set(
+                SystemSet::on_resume(GameState::Playing).with_system(set_physics_active(true)),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(set_physics_active(false)),
+            );
+    }
+}
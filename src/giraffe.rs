@@ -1,15 +1,20 @@
 use std::f32::consts::PI;
 
 use crate::camera::CameraTarget;
-use crate::circular::AngularVelocity;
+use crate::camera_effects::CameraEffect;
 use crate::cursor::CursorWorldPos;
+use crate::game_state::{GameState, RunEntity};
 use crate::in_air::*;
+use crate::layout::Reach;
+use crate::locomotion::{Locomotion, LocomotionState};
 use crate::neck::Neck;
 use crate::neck::NeckPoints;
 use crate::neck::NECK_GROUP;
 use crate::on_floor::*;
 use crate::platform::*;
-use crate::shooting_head::ShootingHeadBundle;
+use crate::rope::Rope;
+use crate::shooting_head::{NeckAttachEvent, ShootingHeadBundle, HEAD_RADIUS};
+use crate::PIXELS_PER_METER;
 use bevy::prelude::*;
 use bevy_inspector_egui::{Inspectable, RegisterInspectable};
 use bevy_rapier2d::prelude::*;
@@ -24,9 +29,43 @@ const RIGHT_DIRECTION: Vec2 = Vec2 { x: 1.0, y: 0.0 };
 
 #[derive(Component, Inspectable)]
 pub struct Giraffe {
-    jump_speed: f32,
+    /// Impulse of a jump, the speed it gives depends on the body's mass
+    jump_impulse: f32,
+    /// Seconds after walking off an edge during which jumping still works
+    coyote_time: f32,
+    /// Seconds a jump press is remembered before landing
+    jump_buffer: f32,
     speed: f32,
+    max_neck_length: f32,
+    reel_speed: f32,
     pub right_direction: Vec2,
+    /// Current walking speed along the floor, negative is left
+    walk_velocity: f32,
+}
+
+impl Giraffe {
+    pub fn reach(&self, gravity: f32) -> Reach {
+        Reach::new(
+            self.jump_impulse / giraffe_mass(),
+            gravity,
+            GIRAFFE_RADIUS,
+            self.max_neck_length + HEAD_RADIUS,
+        )
+    }
+}
+
+#[cfg(test)]
+impl Giraffe {
+    /// Giraffe with the default tuning, for tests that only need one to exist
+    pub(crate) fn for_tests() -> Self {
+        GiraffeBundle::default().giraffe
+    }
+}
+
+/// Mass of the body's ball collider at the default density, in physics units
+fn giraffe_mass() -> f32 {
+    let radius = GIRAFFE_RADIUS / PIXELS_PER_METER;
+    PI * radius * radius
 }
 
 #[derive(Component)]
@@ -35,10 +74,27 @@ struct GiraffeNeckStart(Vec2);
 #[derive(Component)]
 struct PreviousPlatform(Entity);
 
+#[derive(Component)]
+struct JumpTimers {
+    since_grounded: f32,
+    since_jump_pressed: f32,
+}
+
+impl Default for JumpTimers {
+    fn default() -> Self {
+        Self {
+            since_grounded: f32::INFINITY,
+            since_jump_pressed: f32::INFINITY,
+        }
+    }
+}
+
 #[derive(Bundle)]
 struct GiraffeBundle {
     name: Name,
     in_air: InAirBundle,
+    locomotion: Locomotion,
+    jump: JumpTimers,
     sprite_bundle: SpriteBundle,
     giraffe: Giraffe,
     event: ActiveEvents,
@@ -48,6 +104,12 @@ struct GiraffeBundle {
 }
 
 const NECK_NORMAL: f32 = 35.;
+pub const GIRAFFE_RADIUS: f32 = 50.;
+/// How far from the floor surface the giraffe still counts as standing on it
+const SURFACE_SNAP: f32 = 10.;
+const MIN_NECK_LENGTH: f32 = 50.;
+/// Contact impulse above which landing shakes the screen
+const HARD_LANDING_IMPULSE: f32 = 5.;
 
 impl Default for GiraffeBundle {
     fn default() -> Self {
@@ -55,10 +117,17 @@ impl Default for GiraffeBundle {
             name: Name::new("Giraffe"),
             sprite_bundle: SpriteBundle::default(),
             in_air: InAirBundle::default(),
+            locomotion: Locomotion::new(LocomotionState::Airborne),
+            jump: JumpTimers::default(),
             giraffe: Giraffe {
-                jump_speed: 500.0,
+                jump_impulse: 500.0,
+                coyote_time: 0.1,
+                jump_buffer: 0.15,
                 speed: 300.0,
+                max_neck_length: 1500.0,
+                reel_speed: 400.0,
                 right_direction: RIGHT_DIRECTION,
+                walk_velocity: 0.0,
             },
             event: ActiveEvents::COLLISION_EVENTS,
             sleep: Sleeping::disabled(),
@@ -72,110 +141,171 @@ impl Default for GiraffeBundle {
 }
 
 pub fn giraffe_movement(
-    mut query: Query<
-        (
-            Entity,
-            &Giraffe,
-            &mut KinematicCharacterController,
-            &Transform,
-        ),
-        With<OnFloor>,
-    >,
+    mut query: Query<(
+        &mut Giraffe,
+        &mut Locomotion,
+        &mut KinematicCharacterController,
+        &Transform,
+        &OnFloor,
+    )>,
+    surfaces: Query<&PlatformSurface>,
     time: Res<Time>,
     keys: Res<Input<KeyCode>>,
-    mut commands: Commands,
+    rapier_ctx: Res<RapierContext>,
 ) {
-    for (e, g, mut kcc, transform) in query.iter_mut() {
-        for k in keys.get_pressed() {
-            match k {
-                KeyCode::W => {
-                    commands
-                        .entity(e)
-                        .remove::<OnFloorBundle>()
-                        .insert(AddInAirBundle {
-                            impulse: 0. * g.right_direction.perp() * g.jump_speed,
-                        });
-                }
-                KeyCode::A => {
-                    kcc.translation = Some(-g.right_direction * g.speed * time.delta_seconds());
-                }
-                KeyCode::D => {
-                    kcc.translation = Some(g.right_direction * g.speed * time.delta_seconds());
-                }
-                KeyCode::Space => {}
-                _ => {}
+    for (mut g, mut locomotion, mut kcc, transform, on_floor) in query.iter_mut() {
+        let pos = transform.translation.truncate();
+        let floor = on_floor.on_which_floor;
+        let filter = |e: Entity| e == floor;
+
+        let surface = rapier_ctx
+            .project_point(pos, true, QueryFilter::new().predicate(&filter))
+            .filter(|(_, projection)| !projection.is_inside)
+            .map(|(_, projection)| projection.point)
+            .filter(|point| {
+                let distance = point.distance(pos);
+                distance > 0.0 && distance < GIRAFFE_RADIUS + SURFACE_SNAP
+            });
+
+        // Floor moved away or disappeared
+        let point = if let Some(point) = surface {
+            point
+        } else {
+            locomotion.transition(LocomotionState::Airborne);
+            continue;
+        };
+
+        let normal = (pos - point).normalize();
+        g.right_direction = -normal.perp();
+        kcc.up = normal;
+
+        let mut walk = 0.0;
+        if keys.pressed(KeyCode::A) {
+            walk -= 1.0;
+        }
+        if keys.pressed(KeyCode::D) {
+            walk += 1.0;
+        }
+
+        let acceleration = surfaces
+            .get(floor)
+            .copied()
+            .unwrap_or_default()
+            .walk_acceleration();
+        let max_change = acceleration * time.delta_seconds();
+        g.walk_velocity += (walk * g.speed - g.walk_velocity).clamp(-max_change, max_change);
+
+        // Walking along the tangent and pulling back to the surface wraps the giraffe around corners
+        let target = point
+            + normal * GIRAFFE_RADIUS
+            + g.right_direction * g.walk_velocity * time.delta_seconds();
+        kcc.translation = Some(target - pos);
+    }
+}
+
+fn giraffe_jump_system(
+    mut query: Query<(&Giraffe, &mut Locomotion, &mut JumpTimers, Option<&Rope>)>,
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+) {
+    for (g, mut locomotion, mut jump, rope) in query.iter_mut() {
+        jump.since_grounded += time.delta_seconds();
+        jump.since_jump_pressed += time.delta_seconds();
+
+        if locomotion.state().is_grounded() {
+            jump.since_grounded = 0.0;
+        }
+        // W reels the neck in while it's attached, that's not a jump to buffer
+        if keys.just_pressed(KeyCode::W) && rope.is_none() {
+            jump.since_jump_pressed = 0.0;
+        }
+
+        // Coyote time only counts when falling, not after jumping or grabbing with the neck
+        let can_jump = match locomotion.state() {
+            LocomotionState::Grounded(_) | LocomotionState::Airborne => {
+                jump.since_grounded <= g.coyote_time
             }
+            _ => false,
+        };
+
+        if can_jump && jump.since_jump_pressed <= g.jump_buffer {
+            locomotion.launch(g.right_direction.perp() * g.jump_impulse);
+            *jump = JumpTimers::default();
         }
     }
 }
 
 fn neck_control_system(
-    mut query: Query<(Entity, &Giraffe, &GlobalTransform, &GiraffeNeckStart)>,
-    head_query: Query<(&Transform, &GlobalTransform), With<Head>>,
+    query: Query<(&Giraffe, &Transform)>,
+    mut head_query: Query<(&GlobalTransform, &Handle<Image>, &mut Visibility), With<Head>>,
     neck_query: Query<&Neck>,
     keys: Res<Input<KeyCode>>,
     cursor_pos: Res<CursorWorldPos>,
     mut commands: Commands,
-    rapier_ctx: Res<RapierContext>,
 ) {
-    for (e, g, transform, neck_start) in query.iter_mut() {
-        if keys.just_pressed(KeyCode::F) {
-            if let Ok((head_transform, head_glob_transform)) = head_query.get_single() {
-                if let Ok(cursor_pos) = cursor_pos.pos {
-                    let mut transform_copy = *head_transform;
-                    let (scale, rotation, translation) =
-                        head_glob_transform.to_scale_rotation_translation();
-                    transform_copy.translation = translation;
-                    transform_copy.rotation = transform_copy.rotation + rotation;
-                    transform_copy.scale = scale;
-
-                    let velocity = Vec2 {
-                        x: cursor_pos.normalize().x,
-                        y: cursor_pos.normalize().y,
-                    };
-
-                    let ray_start = transform.translation().truncate();
-                    dbg!(ray_start);
-                    let ray_dir = (cursor_pos.truncate() - ray_start).normalize();
-                    dbg!(ray_dir);
-                    let max_toi = 1500.0;
-
-                    if let Some((entity, toi)) = rapier_ctx.cast_ray(
-                        ray_start,
-                        ray_dir,
-                        max_toi,
-                        false,
-                        QueryFilter::new().groups(InteractionGroups::new(
-                            NECK_GROUP,
-                            GIRAFFE_GROUP.complement(),
-                        )),
-                    ) {
-                        let hit_point = ray_start + ray_dir * toi;
-                        if neck_query.iter().count() == 0 {
-                            commands.spawn(NeckBundle::new(hit_point, neck_start.0));
-                            commands.get_entity(e).unwrap().insert(AngularVelocity {
-                                radius: hit_point.distance(ray_start),
-                                speed: 500. / hit_point.distance(ray_start)
-                                    * if hit_point.angle_between(Vec2 { x: 1.0, y: 1.0 }).abs()
-                                        > PI / 2.0
-                                    {
-                                        1.0
-                                    } else {
-                                        -1.0
-                                    },
-                                point: hit_point,
-                            });
-                            commands
-                                .entity(e)
-                                .remove::<OnFloorBundle>()
-                                .insert(AddInAirBundle {
-                                    impulse: 0. * g.right_direction.perp() * g.jump_speed,
-                                });
-                        }
-                    }
+    if !keys.just_pressed(KeyCode::F) || neck_query.iter().count() > 0 {
+        return;
+    }
 
-                    commands.spawn(ShootingHeadBundle::new(transform_copy, velocity));
-                }
+    if let Ok((g, transform)) = query.get_single() {
+        if let Ok((head_glob_transform, texture, mut visibility)) = head_query.get_single_mut() {
+            if let Ok(cursor_pos) = cursor_pos.pos {
+                let head_pos = head_glob_transform.translation();
+                let direction = (cursor_pos - transform.translation).truncate();
+
+                let neck = commands
+                    .spawn((
+                        NeckBundle::new(head_pos.truncate(), transform.translation.truncate()),
+                        RunEntity,
+                    ))
+                    .id();
+                commands.spawn((
+                    ShootingHeadBundle::new(Transform::from_translation(head_pos), direction, neck)
+                        .with_max_range(g.max_neck_length)
+                        .with_texture(texture.clone()),
+                    RunEntity,
+                ));
+                visibility.is_visible = false;
+            }
+        }
+    }
+}
+
+fn neck_attach_system(
+    mut events: EventReader<NeckAttachEvent>,
+    mut query: Query<(Entity, &Giraffe, &Transform, &mut Locomotion)>,
+    mut commands: Commands,
+) {
+    for event in events.iter() {
+        if let Ok((e, g, transform, mut locomotion)) = query.get_single_mut() {
+            commands.entity(e).insert(Rope::new(
+                event
+                    .point
+                    .distance(transform.translation.truncate())
+                    .min(g.max_neck_length),
+            ));
+            locomotion.launch(Vec2::ZERO);
+        }
+    }
+}
+
+fn neck_reel_system(
+    mut query: Query<(&Giraffe, &mut Rope)>,
+    neck_query: Query<&NeckPoints>,
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+) {
+    if let Ok(neck) = neck_query.get_single() {
+        for (g, mut rope) in query.iter_mut() {
+            let reel = g.reel_speed * time.delta_seconds();
+            let min_length = neck.wrapped_length() + MIN_NECK_LENGTH;
+
+            if keys.pressed(KeyCode::W) {
+                // Reel in from the actual length, so a slack neck tightens right away
+                rope.length = (rope.length.min(neck.length()) - reel).max(min_length);
+            }
+            if keys.pressed(KeyCode::S) {
+                rope.length = (rope.length + reel).min(g.max_neck_length);
             }
         }
     }
@@ -201,7 +331,10 @@ fn giraffe_turn_system(
 ) {
     if let Ok((g, t, mut neckstart)) = giraffe.get_single_mut() {
         if let Ok( (mut transform, mut sprite)) = query.get_single_mut() {
-            transform.rotation = Quat::from_rotation_arc_2d(RIGHT_DIRECTION.normalize(), g.right_direction.normalize());
+            transform.rotation = Quat::from_rotation_z(
+                g.right_direction.y.atan2(g.right_direction.x)
+                    - RIGHT_DIRECTION.y.atan2(RIGHT_DIRECTION.x),
+            );
 
             if let Ok(mouse_pos) = mouse_pos.pos {
                 if g.right_direction
@@ -210,9 +343,9 @@ fn giraffe_turn_system(
                     < PI / 2.0
                 {
                     sprite.flip_x = false;
-                    neck.0 = NECK_NORMAL * g.right_direction;
+                    neckstart.0 = NECK_NORMAL * g.right_direction;
                 } else {
-                    neck.0 = -NECK_NORMAL * g.right_direction;
+                    neckstart.0 = -NECK_NORMAL * g.right_direction;
                     sprite.flip_x = true;
                 }
             }
@@ -244,6 +377,7 @@ fn spawn_giraffe(mut commands: Commands, handles: Res<AssetServer>) {
     commands
         .spawn((
             GiraffeBundle::default(),
+            RunEntity,
             CameraTarget,
             NeckTarget,
             CollisionGroups::new(
@@ -272,14 +406,24 @@ fn spawn_giraffe(mut commands: Commands, handles: Res<AssetServer>) {
         });
 }
 
+/// Drops the neck when the flying giraffe bumps into something, keeping its momentum
 fn remove_neck_system(
-    mut query: Query<(Entity), With<Neck>>,
-    mut giraffe_query: Query<Entity, (With<InAir>, With<Giraffe>)>,
+    query: Query<Entity, With<Neck>>,
+    giraffe_query: Query<(Entity, &Locomotion), (With<InAir>, With<Giraffe>)>,
     rapier_ctx: Res<RapierContext>,
     mut commands: Commands,
 ) {
-    for e in giraffe_query.iter() {
-        if rapier_ctx.contacts_with(e).count() > 0 {
+    for (e, locomotion) in giraffe_query.iter() {
+        // Grabbing from the ground starts while still touching it
+        if locomotion.state() == LocomotionState::Launching {
+            continue;
+        }
+
+        // Pairs with only overlapping bounding boxes have no contact points
+        let touching = rapier_ctx
+            .contacts_with(e)
+            .any(|pair| pair.manifolds().any(|manifold| manifold.num_points() > 0));
+        if touching {
             if let Ok(neck) = query.get_single() {
                 commands.entity(neck).despawn();
             }
@@ -287,14 +431,27 @@ fn remove_neck_system(
     }
 }
 
+/// Lets go of the neck on demand, the giraffe flies on with the speed it had
+fn release_neck_system(
+    query: Query<Entity, With<Neck>>,
+    keys: Res<Input<KeyCode>>,
+    mut commands: Commands,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        for neck in query.iter() {
+            commands.entity(neck).despawn();
+        }
+    }
+}
+
 fn giraffe_hit_floor(
-    mut giraffe: Query<(Entity, &InAir, &mut Giraffe)>,
-    platforms: Query<(&Platform, &Transform)>,
+    mut giraffe: Query<(Entity, &mut Locomotion, &mut Giraffe), With<InAir>>,
+    platforms: Query<Option<&PlatformSurface>, With<Platform>>,
     rapier_context: Res<RapierContext>,
-    mut commands: Commands,
+    mut camera_effects: EventWriter<CameraEffect>,
 ) {
-    for (e, ia, mut g) in giraffe.iter_mut() {
-        if ia.timer.finished() {
+    for (e, mut locomotion, mut g) in giraffe.iter_mut() {
+        if locomotion.state() != LocomotionState::Launching {
             for contact_pair in rapier_context.contacts_with(e) {
                 let other_collider = if contact_pair.collider1() == e {
                     contact_pair.collider2()
@@ -302,34 +459,30 @@ fn giraffe_hit_floor(
                     contact_pair.collider1()
                 };
 
-                if platforms.contains(other_collider) {
-                    if contact_pair.manifolds().last().unwrap().points().len() > 0 {
+                // Bouncy platforms are left to the physics to bounce off
+                let standable = match platforms.get(other_collider) {
+                    Ok(surface) => surface.map_or(true, PlatformSurface::can_stand_on),
+                    Err(_) => false,
+                };
+                if standable {
+                    let manifold = contact_pair.manifolds().last().unwrap();
+                    if let Some(contact) = manifold.points().last() {
                         let point = if contact_pair.collider1() == e {
-                            contact_pair
-                                .manifolds()
-                                .last()
-                                .unwrap()
-                                .points()
-                                .last()
-                                .unwrap()
-                                .local_p1()
+                            contact.local_p1()
                         } else {
-                            contact_pair
-                                .manifolds()
-                                .last()
-                                .unwrap()
-                                .points()
-                                .last()
-                                .unwrap()
-                                .local_p2()
+                            contact.local_p2()
                         };
 
-                        commands
-                            .entity(e)
-                            .remove::<InAirBundle>()
-                            .insert(AddOnFloorBundle {
-                                on_which_floor: other_collider,
+                        if contact.impulse() > HARD_LANDING_IMPULSE {
+                            let hardness = contact.impulse() / HARD_LANDING_IMPULSE;
+                            camera_effects.send(CameraEffect::Shake {
+                                trauma: (0.3 * hardness).min(0.8),
                             });
+                            camera_effects.send(CameraEffect::HitStop { seconds: 0.05 });
+                        }
+
+                        locomotion.transition(LocomotionState::Grounded(other_collider));
+                        g.walk_velocity = 0.0;
                         g.right_direction = point.clamp_length(1.0, 1.0).perp();
                         return;
                     }
@@ -343,14 +496,21 @@ pub struct GiraffePlugin;
 
 impl Plugin for GiraffePlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(spawn_giraffe)
-            .add_system(giraffe_movement)
-            .add_system(giraffe_hit_floor)
-            .add_system(head_turn_system)
-            .add_system(giraffe_turn_system)
-            .add_system(keep_neck_at_player_system)
-            .add_system(remove_neck_system)
-            .add_system(neck_control_system)
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_giraffe))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(giraffe_movement)
+                    .with_system(giraffe_jump_system.after(giraffe_movement))
+                    .with_system(giraffe_hit_floor)
+                    .with_system(head_turn_system)
+                    .with_system(giraffe_turn_system)
+                    .with_system(keep_neck_at_player_system)
+                    .with_system(remove_neck_system)
+                    .with_system(release_neck_system)
+                    .with_system(neck_control_system)
+                    .with_system(neck_attach_system)
+                    .with_system(neck_reel_system),
+            )
             //DEBUG
             .register_inspectable::<Giraffe>();
     }
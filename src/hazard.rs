@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use serde::Deserialize;
+
+use crate::camera::{camera_zoom_system, MainCamera};
+use crate::difficulty::DifficultyProfile;
+use crate::game_state::{GameState, RunEntity};
+use crate::giraffe::{Giraffe, GIRAFFE_RADIUS};
+use crate::platform_spawner::MaxHeightComponent;
+
+/// Above platforms and the neck, so whatever the hazard swallowed disappears in it
+const HAZARD_Z: f32 = 10.0;
+const HAZARD_COLOR: Color = Color::rgba(1.0, 0.6, 0.5, 0.9);
+
+pub struct HazardPlugin;
+
+/// Settings of the rising hazard, its speed comes from the difficulty bands
+#[derive(Deserialize, Clone)]
+pub struct HazardSettings {
+    /// Seconds after the start of a run before the hazard starts rising
+    pub grace_period: f32,
+    /// How fast the hazard gets to the speed of the current band, in pixels per second squared
+    pub acceleration: f32,
+    /// Where the hazard starts, in pixels below the bottom of the window
+    pub start_below: f32,
+}
+
+#[derive(Component)]
+pub struct RisingHazard {
+    /// Height of the hazard's top edge
+    pub height: f32,
+    speed: f32,
+    elapsed: f32,
+}
+
+fn spawn_hazard(
+    mut commands: Commands,
+    difficulty: Res<DifficultyProfile>,
+    handles: Res<AssetServer>,
+    windows: Res<Windows>,
+) {
+    let (settings, window) = match (&difficulty.hazard, windows.get_primary()) {
+        (Some(settings), Some(window)) => (settings, window),
+        _ => return,
+    };
+    let height = -window.height() / 2.0 - settings.start_below;
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: HAZARD_COLOR,
+                custom_size: Some(Vec2::new(window.width(), window.height() * 2.0)),
+                anchor: Anchor::TopCenter,
+                ..default()
+            },
+            texture: handles.load("tlo_roz/small/mgla.png"),
+            transform: Transform::from_xyz(0.0, height, HAZARD_Z),
+            ..default()
+        },
+        RisingHazard {
+            height,
+            speed: 0.0,
+            elapsed: 0.0,
+        },
+        Name::new("Rising hazard"),
+        RunEntity,
+    ));
+}
+
+fn rise_hazard(
+    mut query: Query<(&mut RisingHazard, &mut Transform)>,
+    max_height_query: Query<&MaxHeightComponent>,
+    difficulty: Res<DifficultyProfile>,
+    time: Res<Time>,
+) {
+    let settings = if let Some(settings) = &difficulty.hazard {
+        settings
+    } else {
+        return;
+    };
+
+    for (mut hazard, mut transform) in query.iter_mut() {
+        hazard.elapsed += time.delta_seconds();
+        if hazard.elapsed < settings.grace_period {
+            continue;
+        }
+
+        // Gets faster as the player gets into harder bands
+        let progress = max_height_query
+            .get_single()
+            .map_or(hazard.height, |max| max.height);
+        let target_speed = difficulty.band_at(progress).hazard_speed;
+        let max_change = settings.acceleration * time.delta_seconds();
+        hazard.speed += (target_speed - hazard.speed).clamp(-max_change, max_change);
+
+        hazard.height += hazard.speed * time.delta_seconds();
+        transform.translation.y = hazard.height;
+    }
+}
+
+fn scale_hazard(
+    mut query: Query<&mut Transform, With<RisingHazard>>,
+    camera_query: Query<&OrthographicProjection, With<MainCamera>>,
+) {
+    if let Ok(projection) = camera_query.get_single() {
+        for mut transform in query.iter_mut() {
+            // Grow with the zoom so the hazard still covers the whole view when zoomed out
+            transform.scale.x = projection.scale;
+            transform.scale.y = projection.scale;
+        }
+    }
+}
+
+fn hazard_touch(
+    giraffe_query: Query<&Transform, With<Giraffe>>,
+    hazard_query: Query<&RisingHazard>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if let (Ok(giraffe), Ok(hazard)) = (giraffe_query.get_single(), hazard_query.get_single()) {
+        if giraffe.translation.y - GIRAFFE_RADIUS < hazard.height {
+            let _ = state.set(GameState::GameOver);
+        }
+    }
+}
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(spawn_hazard))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(rise_hazard)
+                    .with_system(scale_hazard.after(camera_zoom_system))
+                    .with_system(hazard_touch.after(rise_hazard)),
+            );
+    }
+}
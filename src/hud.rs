@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+use crate::platform_spawner::PlatformSeed;
+use crate::score::Score;
+
+const HUD_FONT_SIZE: f32 = 32.0;
+const HUD_COLOR: Color = Color::WHITE;
+
+pub struct HudPlugin;
+
+#[derive(Component)]
+struct HeightText;
+
+fn setup_hud(mut commands: Commands, server: Res<AssetServer>) {
+    let style = TextStyle {
+        font: server.load("fonts/DejaVuSans-Bold.ttf"),
+        font_size: HUD_FONT_SIZE,
+        color: HUD_COLOR,
+    };
+
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new("Height: ", style.clone()),
+            TextSection::from_style(style.clone()),
+            TextSection::new("\nBest: ", style.clone()),
+            TextSection::from_style(style.clone()),
+            TextSection::new("\nSeed: ", style.clone()),
+            TextSection::from_style(style),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(10.0),
+                left: Val::Px(70.0),
+                ..default()
+            },
+            ..default()
+        }),
+        HeightText,
+    ));
+}
+
+fn update_hud(
+    score: Res<Score>,
+    seed: Res<PlatformSeed>,
+    mut query: Query<&mut Text, With<HeightText>>,
+) {
+    if !score.is_changed() && !seed.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.sections[1].value = format!("{:.1} m", score.height);
+        text.sections[3].value = format!("{:.1} m", score.best);
+        text.sections[5].value = seed.0.to_string();
+    }
+}
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_hud).add_system(update_hud);
+    }
+}
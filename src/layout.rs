@@ -0,0 +1,373 @@
+use bevy::math::Rect;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::moving_platform::PlatformPath;
+use crate::platform::{PlatformKind, PlatformSurface};
+
+/// How many random positions are tried before falling back to a safe one
+const MAX_ATTEMPTS: usize = 16;
+/// Part of the reach actually used, so reachable platforms aren't frame perfect
+const REACH_MARGIN: f32 = 0.9;
+/// Least the next platform's height moves up, so bad params can't stall generation
+const MIN_STEP: f32 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlatformSpec {
+    pub pos: Vec2,
+    pub size: Vec2,
+    pub kind: PlatformKind,
+    pub surface: PlatformSurface,
+    /// Picks the look of the platform
+    pub variant: u32,
+    /// Offsets from `pos` the platform's center moves over, empty for platforms staying in place
+    pub travel: Rect,
+}
+
+/// Offsets from its start a platform of the given kind moves over
+fn kind_travel(kind: PlatformKind) -> Rect {
+    PlatformPath::for_kind(kind, Vec2::ZERO).map_or(Rect::default(), |path| path.bounds())
+}
+
+/// Widest gap between two axis-aligned rectangles along each axis, zero where they always overlap
+///
+/// The centers can be anywhere within their travel, moved independently of each other.
+fn widest_gap(
+    (center_a, size_a, travel_a): (Vec2, Vec2, Rect),
+    (center_b, size_b, travel_b): (Vec2, Vec2, Rect),
+) -> Vec2 {
+    let offset = center_b - center_a;
+    let farthest = (offset + travel_b.min - travel_a.max)
+        .abs()
+        .max((offset + travel_b.max - travel_a.min).abs());
+    (farthest - (size_a + size_b) / 2.0).max(Vec2::ZERO)
+}
+
+impl PlatformSpec {
+    fn top(&self) -> f32 {
+        self.pos.y + self.size.y / 2.0
+    }
+
+    fn lowest_top(&self) -> f32 {
+        self.top() + self.travel.min.y
+    }
+
+    fn highest_top(&self) -> f32 {
+        self.top() + self.travel.max.y
+    }
+
+    fn horizontal_gap(&self, other: &PlatformSpec) -> f32 {
+        widest_gap(
+            (self.pos, self.size, self.travel),
+            (other.pos, other.size, other.travel),
+        )
+        .x
+    }
+}
+
+/// How far the giraffe can get from a platform
+#[derive(Clone, Copy, Debug)]
+pub struct Reach {
+    /// How much higher than its take-off the giraffe can land
+    pub jump_height: f32,
+    /// How far past a platform's edge a jump still lands, about the body's size as jumps go up
+    pub jump_overhang: f32,
+    /// Height of the body's center above the platform it stands on, the head is shot from there
+    pub body_height: f32,
+    /// How far from the body the flying head can still grab a platform
+    pub grapple_range: f32,
+}
+
+impl Reach {
+    /// `jump_speed` is the speed the jump leaves the ground with
+    pub fn new(jump_speed: f32, gravity: f32, body_radius: f32, grapple_range: f32) -> Self {
+        Self {
+            jump_height: jump_speed * jump_speed / (2.0 * gravity) * REACH_MARGIN,
+            jump_overhang: body_radius,
+            body_height: body_radius,
+            grapple_range: grapple_range * REACH_MARGIN,
+        }
+    }
+
+    /// Whether `to` is in reach from `from` wherever both are along their travel
+    ///
+    /// Jumps and the neck both start from standing on `from`, and the neck only holds on to
+    /// surfaces it can anchor to.
+    pub fn can_reach(&self, from: &PlatformSpec, to: &PlatformSpec) -> bool {
+        if !from.surface.can_stand_on() {
+            return false;
+        }
+
+        let by_jump = to.highest_top() - from.lowest_top() <= self.jump_height
+            && from.horizontal_gap(to) <= self.jump_overhang;
+
+        // The head is shot from the body standing anywhere along the top of `from`
+        let body = Vec2::new(from.pos.x, from.top() + self.body_height);
+        let body_span = Vec2::new(from.size.x, 0.0);
+        let by_grapple = to.surface.can_anchor()
+            && widest_gap((body, body_span, from.travel), (to.pos, to.size, to.travel)).length()
+                <= self.grapple_range;
+
+        by_jump || by_grapple
+    }
+}
+
+pub struct LayoutParams {
+    /// Width of the arena the platforms have to fit in
+    pub width: f32,
+    /// Vertical distance between platforms
+    pub spacing: f32,
+    /// Part of the arena width platforms are spread over, centered in the arena
+    pub spread: f32,
+    pub platform_size: Vec2,
+    /// Platform kinds with their relative weights
+    pub kinds: Vec<(PlatformKind, f32)>,
+    /// Platform surfaces with their relative weights
+    pub surfaces: Vec<(PlatformSurface, f32)>,
+}
+
+fn pick_weighted<T: Copy>(rng: &mut StdRng, options: &[(T, f32)], fallback: T) -> T {
+    let total: f32 = options.iter().map(|(_, weight)| weight.max(0.0)).sum();
+    let mut roll = rng.gen::<f32>() * total;
+
+    for (option, weight) in options {
+        roll -= weight.max(0.0);
+        if roll < 0.0 {
+            return *option;
+        }
+    }
+    options.last().map_or(fallback, |(option, _)| *option)
+}
+
+/// Platforms between `from_height` and `to_height`, each reachable from `previous` or an earlier one
+///
+/// The same seed and range always give the same layout.
+pub fn generate_layout(
+    seed: u64,
+    from_height: f32,
+    to_height: f32,
+    previous: &[PlatformSpec],
+    params: &LayoutParams,
+    reach: &Reach,
+) -> Vec<PlatformSpec> {
+    let mut rng = StdRng::seed_from_u64(seed ^ from_height.to_bits() as u64);
+    let mut placed: Vec<PlatformSpec> = Vec::new();
+
+    let half_range = ((params.width * params.spread - params.platform_size.x) / 2.0).max(0.0);
+    let mut y = from_height + params.spacing;
+
+    while y < to_height {
+        let reachable = |candidate: &PlatformSpec| {
+            previous
+                .iter()
+                .chain(placed.iter())
+                .any(|from| reach.can_reach(from, candidate))
+        };
+
+        let kind = pick_weighted(&mut rng, &params.kinds, PlatformKind::Static);
+        let surface = pick_weighted(&mut rng, &params.surfaces, PlatformSurface::Normal);
+        let variant = rng.gen();
+        let travel = kind_travel(kind);
+
+        // Moving platforms have to stay in the arena along their whole path
+        let (min_x, max_x) = (-half_range - travel.min.x, half_range - travel.max.x);
+
+        let random = (0..MAX_ATTEMPTS)
+            .map(|_| PlatformSpec {
+                pos: Vec2::new(
+                    if min_x < max_x {
+                        rng.gen_range(min_x..=max_x)
+                    } else {
+                        (min_x + max_x) / 2.0
+                    },
+                    y,
+                ),
+                size: params.platform_size,
+                kind,
+                surface,
+                variant,
+                travel,
+            })
+            .find(|candidate| reachable(candidate));
+
+        let platform = random.unwrap_or_else(|| {
+            // Nothing random fits, so stack a plain static platform right above the highest
+            // one the giraffe can stand on, over the middle of its travel and within reach of
+            // its lowest point
+            let below = previous
+                .iter()
+                .chain(placed.iter())
+                .filter(|platform| platform.surface.can_stand_on())
+                .copied()
+                .max_by(|a, b| a.top().total_cmp(&b.top()))
+                .unwrap_or(PlatformSpec {
+                    pos: Vec2::new(0.0, from_height),
+                    size: params.platform_size,
+                    kind: PlatformKind::Static,
+                    surface: PlatformSurface::Normal,
+                    variant,
+                    travel: Rect::default(),
+                });
+            // Kept a bit under the limit, so rounding can't put it out of reach
+            let max_rise = reach.jump_height.max(reach.grapple_range) * REACH_MARGIN;
+
+            PlatformSpec {
+                pos: Vec2::new(
+                    (below.pos.x + below.travel.center().x).clamp(-half_range, half_range),
+                    y.min(below.lowest_top() + max_rise - params.platform_size.y / 2.0),
+                ),
+                size: params.platform_size,
+                kind: PlatformKind::Static,
+                surface: PlatformSurface::Normal,
+                variant,
+                travel: Rect::default(),
+            }
+        });
+
+        y = (platform.pos.y + params.spacing).max(y + MIN_STEP);
+        placed.push(platform);
+    }
+
+    placed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floor() -> PlatformSpec {
+        PlatformSpec {
+            pos: Vec2::new(0.0, -450.0),
+            size: Vec2::new(900.0, 40.0),
+            kind: PlatformKind::Static,
+            surface: PlatformSurface::Normal,
+            variant: 0,
+            travel: Rect::default(),
+        }
+    }
+
+    fn params() -> LayoutParams {
+        LayoutParams {
+            width: 900.0,
+            spacing: 250.0,
+            spread: 1.0,
+            platform_size: Vec2::new(250.0, 100.0),
+            kinds: vec![
+                (PlatformKind::Static, 3.0),
+                (PlatformKind::PingPong, 1.0),
+                (PlatformKind::Circling, 1.0),
+                (PlatformKind::Waypoints, 1.0),
+                (PlatformKind::Crumbling, 1.0),
+            ],
+            surfaces: vec![
+                (PlatformSurface::Normal, 3.0),
+                (PlatformSurface::Bouncy, 1.0),
+                (PlatformSurface::Icy, 1.0),
+            ],
+        }
+    }
+
+    /// About what the default giraffe can do
+    fn giraffe_reach() -> Reach {
+        Reach::new(640.0, 981.0, 50.0, 1525.0)
+    }
+
+    /// Short enough that most random candidates are out of reach
+    fn short_reach() -> Reach {
+        Reach::new(500.0, 981.0, 50.0, 250.0)
+    }
+
+    fn assert_reachable(previous: &[PlatformSpec], layout: &[PlatformSpec], reach: &Reach) {
+        for (i, platform) in layout.iter().enumerate() {
+            let reachable = previous
+                .iter()
+                .chain(&layout[..i])
+                .any(|from| reach.can_reach(from, platform));
+            assert!(reachable, "platform {} at {:?} is out of reach", i, platform.pos);
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_layout() {
+        let layout =
+            |seed| generate_layout(seed, 0.0, 5000.0, &[floor()], &params(), &giraffe_reach());
+
+        let first = layout(42);
+        assert!(!first.is_empty());
+        assert_eq!(first, layout(42));
+        assert_ne!(first, layout(43));
+    }
+
+    #[test]
+    fn every_platform_is_reachable() {
+        for seed in 0..50 {
+            for reach in [giraffe_reach(), short_reach()] {
+                let layout = generate_layout(seed, 0.0, 5000.0, &[floor()], &params(), &reach);
+                assert!(!layout.is_empty());
+                assert_reachable(&[floor()], &layout, &reach);
+            }
+        }
+    }
+
+    #[test]
+    fn surfaces_limit_reach() {
+        let reach = giraffe_reach();
+        let with_surface = |surface, y| PlatformSpec {
+            pos: Vec2::new(0.0, y),
+            surface,
+            size: params().platform_size,
+            ..floor()
+        };
+        // Only the neck gets this high
+        let high = 1000.0;
+        assert!(reach.jump_height < high);
+
+        let normal = with_surface(PlatformSurface::Normal, 0.0);
+        assert!(reach.can_reach(&normal, &with_surface(PlatformSurface::Normal, high)));
+        assert!(!reach.can_reach(&normal, &with_surface(PlatformSurface::Icy, high)));
+        assert!(reach.can_reach(&normal, &with_surface(PlatformSurface::Icy, 100.0)));
+
+        let bouncy = with_surface(PlatformSurface::Bouncy, 0.0);
+        assert!(!reach.can_reach(&bouncy, &with_surface(PlatformSurface::Normal, 100.0)));
+        assert!(!reach.can_reach(&bouncy, &with_surface(PlatformSurface::Normal, high)));
+    }
+
+    #[test]
+    fn moving_platforms_stay_in_the_arena() {
+        let params = params();
+        let half_width = params.width / 2.0;
+
+        for seed in 0..20 {
+            let layout = generate_layout(seed, 0.0, 5000.0, &[floor()], &params, &giraffe_reach());
+            for platform in &layout {
+                let left = platform.pos.x + platform.travel.min.x - platform.size.x / 2.0;
+                let right = platform.pos.x + platform.travel.max.x + platform.size.x / 2.0;
+                assert!(left >= -half_width && right <= half_width, "{:?}", platform);
+            }
+        }
+    }
+
+    #[test]
+    fn too_short_reach_falls_back() {
+        // Neither a jump nor the neck gets a platform up by the spacing
+        let reach = Reach::new(300.0, 981.0, 50.0, 0.0);
+        let layout = generate_layout(7, 0.0, 2000.0, &[floor()], &params(), &reach);
+
+        assert!(!layout.is_empty());
+        assert_reachable(&[floor()], &layout, &reach);
+        assert!(layout.windows(2).all(|pair| pair[1].pos.y > pair[0].pos.y));
+    }
+
+    #[test]
+    fn degenerate_params_terminate() {
+        let params = LayoutParams {
+            spacing: 0.0,
+            ..params()
+        };
+        let reach = Reach::new(0.0, 981.0, 0.0, 0.0);
+        let layout = generate_layout(7, 0.0, 1000.0, &[floor()], &params, &reach);
+
+        assert!(layout.len() <= (1000.0 / MIN_STEP) as usize + 1);
+    }
+}
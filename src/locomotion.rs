@@ -0,0 +1,309 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::game_state::GameState;
+use crate::in_air::InAirBundle;
+use crate::on_floor::{OnFloor, OnFloorBundle};
+use crate::rope::Rope;
+
+/// Time after leaving the ground during which landing is ignored
+const LAUNCH_TIME: f32 = 0.1;
+
+pub struct LocomotionPlugin;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LocomotionState {
+    /// Walking on the given platform
+    Grounded(Entity),
+    /// Just jumped or grabbed a platform, can't land until the launch timer finishes
+    Launching,
+    /// Flying freely
+    Airborne,
+    /// Hanging on the neck
+    Swinging,
+}
+
+impl LocomotionState {
+    pub fn is_grounded(&self) -> bool {
+        matches!(self, LocomotionState::Grounded(_))
+    }
+}
+
+/// Sent every time a locomotion state is left for another one
+pub struct LocomotionEvent {
+    pub entity: Entity,
+    pub from: LocomotionState,
+    pub to: LocomotionState,
+}
+
+#[derive(Component)]
+pub struct Locomotion {
+    state: LocomotionState,
+    next: Option<LocomotionState>,
+    impulse: Vec2,
+    launch_timer: Timer,
+}
+
+impl Locomotion {
+    pub fn new(state: LocomotionState) -> Self {
+        Self {
+            state,
+            next: None,
+            impulse: Vec2::ZERO,
+            launch_timer: Timer::new(Duration::from_secs_f32(LAUNCH_TIME), TimerMode::Once),
+        }
+    }
+
+    pub fn state(&self) -> LocomotionState {
+        self.state
+    }
+
+    /// Requests a transition applied at the start of next frame, the last request in a frame wins
+    pub fn transition(&mut self, next: LocomotionState) {
+        self.next = Some(next);
+    }
+
+    /// Leaves the ground or the current flight with the given impulse
+    pub fn launch(&mut self, impulse: Vec2) {
+        self.impulse = impulse;
+        self.transition(LocomotionState::Launching);
+    }
+}
+
+fn on_exit(entity: Entity, from: LocomotionState, to: LocomotionState, commands: &mut Commands) {
+    match (from, to) {
+        (LocomotionState::Grounded(_), LocomotionState::Grounded(_)) => {}
+        (LocomotionState::Grounded(_), _) => {
+            commands
+                .entity(entity)
+                .remove::<OnFloorBundle>()
+                .remove::<KinematicCharacterControllerOutput>();
+        }
+        (_, LocomotionState::Grounded(_)) => {
+            commands.entity(entity).remove::<InAirBundle>();
+        }
+        _ => {}
+    }
+}
+
+fn on_enter(
+    entity: Entity,
+    from: LocomotionState,
+    locomotion: &mut Locomotion,
+    commands: &mut Commands,
+) {
+    match locomotion.state {
+        LocomotionState::Grounded(floor) => {
+            if from.is_grounded() {
+                commands.entity(entity).insert(OnFloor {
+                    on_which_floor: floor,
+                });
+            } else {
+                commands.entity(entity).insert(OnFloorBundle::new(floor));
+            }
+        }
+        LocomotionState::Launching => {
+            locomotion.launch_timer.reset();
+
+            let impulse = std::mem::take(&mut locomotion.impulse);
+            if from.is_grounded() {
+                commands.entity(entity).insert(InAirBundle::new(impulse));
+            } else {
+                commands
+                    .entity(entity)
+                    .insert(ExternalImpulse { impulse, ..default() });
+            }
+        }
+        LocomotionState::Airborne | LocomotionState::Swinging => {
+            if from.is_grounded() {
+                commands.entity(entity).insert(InAirBundle::default());
+            }
+        }
+    }
+}
+
+fn apply_transitions(
+    mut query: Query<(Entity, &mut Locomotion)>,
+    mut events: EventWriter<LocomotionEvent>,
+    mut commands: Commands,
+) {
+    for (entity, mut locomotion) in query.iter_mut() {
+        if let Some(next) = locomotion.next.take() {
+            let from = locomotion.state;
+            if from == next && next != LocomotionState::Launching {
+                continue;
+            }
+
+            on_exit(entity, from, next, &mut commands);
+            locomotion.state = next;
+            on_enter(entity, from, &mut locomotion, &mut commands);
+
+            events.send(LocomotionEvent {
+                entity,
+                from,
+                to: next,
+            });
+        }
+    }
+}
+
+fn log_transitions(mut events: EventReader<LocomotionEvent>) {
+    for event in events.iter() {
+        debug!("{:?} went from {:?} to {:?}", event.entity, event.from, event.to);
+    }
+}
+
+fn update_locomotion(mut query: Query<(&mut Locomotion, Option<&Rope>)>, time: Res<Time>) {
+    for (mut locomotion, rope) in query.iter_mut() {
+        let flying = if rope.is_some() {
+            LocomotionState::Swinging
+        } else {
+            LocomotionState::Airborne
+        };
+
+        match locomotion.state {
+            LocomotionState::Launching => {
+                locomotion.launch_timer.tick(time.delta());
+                if locomotion.launch_timer.finished() && locomotion.next.is_none() {
+                    locomotion.transition(flying);
+                }
+            }
+            LocomotionState::Airborne | LocomotionState::Swinging => {
+                if locomotion.state != flying && locomotion.next.is_none() {
+                    locomotion.transition(flying);
+                }
+            }
+            LocomotionState::Grounded(_) => {}
+        }
+    }
+}
+
+impl Plugin for LocomotionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<LocomotionEvent>()
+            .add_system_to_stage(CoreStage::PreUpdate, apply_transitions)
+            .add_system(log_transitions)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(update_locomotion),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::event::ManualEventReader;
+
+    use super::*;
+    use crate::in_air::InAir;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_state(GameState::Playing)
+            .add_plugin(LocomotionPlugin);
+        app
+    }
+
+    fn spawn_grounded(app: &mut App) -> (Entity, Entity) {
+        let floor = app.world.spawn_empty().id();
+        let giraffe = app
+            .world
+            .spawn((
+                Locomotion::new(LocomotionState::Grounded(floor)),
+                OnFloorBundle::new(floor),
+            ))
+            .id();
+        (giraffe, floor)
+    }
+
+    fn locomotion(app: &mut App, entity: Entity) -> Mut<Locomotion> {
+        app.world.get_mut::<Locomotion>(entity).unwrap()
+    }
+
+    /// Checks the entity has exactly one of the bundles and tells which one
+    fn has_floor_bundle(app: &App, entity: Entity) -> bool {
+        let entity = app.world.entity(entity);
+        let on_floor =
+            entity.contains::<OnFloor>() && entity.contains::<KinematicCharacterController>();
+        let in_air = entity.contains::<InAir>() && entity.contains::<RigidBody>();
+        assert_ne!(on_floor, in_air);
+        on_floor
+    }
+
+    fn read_events(
+        app: &App,
+        reader: &mut ManualEventReader<LocomotionEvent>,
+    ) -> Vec<(Entity, LocomotionState, LocomotionState)> {
+        let events = app.world.resource::<Events<LocomotionEvent>>();
+        reader
+            .iter(events)
+            .map(|event| (event.entity, event.from, event.to))
+            .collect()
+    }
+
+    #[test]
+    fn launch_and_land_swap_bundles() {
+        let mut app = test_app();
+        let mut reader = ManualEventReader::default();
+        let (giraffe, floor) = spawn_grounded(&mut app);
+
+        app.update();
+        assert!(has_floor_bundle(&app, giraffe));
+        assert!(read_events(&app, &mut reader).is_empty());
+
+        locomotion(&mut app, giraffe).launch(Vec2::new(0.0, 10.0));
+        app.update();
+        assert_eq!(locomotion(&mut app, giraffe).state(), LocomotionState::Launching);
+        assert!(!has_floor_bundle(&app, giraffe));
+        assert_eq!(
+            app.world.get::<ExternalImpulse>(giraffe).unwrap().impulse,
+            Vec2::new(0.0, 10.0)
+        );
+        assert_eq!(
+            read_events(&app, &mut reader),
+            vec![(giraffe, LocomotionState::Grounded(floor), LocomotionState::Launching)]
+        );
+
+        locomotion(&mut app, giraffe).transition(LocomotionState::Grounded(floor));
+        app.update();
+        assert!(locomotion(&mut app, giraffe).state().is_grounded());
+        assert!(has_floor_bundle(&app, giraffe));
+        assert_eq!(
+            read_events(&app, &mut reader),
+            vec![(giraffe, LocomotionState::Launching, LocomotionState::Grounded(floor))]
+        );
+    }
+
+    #[test]
+    fn last_transition_in_a_frame_wins() {
+        let mut app = test_app();
+        let mut reader = ManualEventReader::default();
+        let (giraffe, floor) = spawn_grounded(&mut app);
+
+        locomotion(&mut app, giraffe).transition(LocomotionState::Airborne);
+        locomotion(&mut app, giraffe).transition(LocomotionState::Swinging);
+        app.update();
+
+        assert_eq!(locomotion(&mut app, giraffe).state(), LocomotionState::Swinging);
+        assert!(!has_floor_bundle(&app, giraffe));
+        assert_eq!(
+            read_events(&app, &mut reader),
+            vec![(giraffe, LocomotionState::Grounded(floor), LocomotionState::Swinging)]
+        );
+    }
+
+    #[test]
+    fn staying_in_a_state_sends_no_event() {
+        let mut app = test_app();
+        let mut reader = ManualEventReader::default();
+        let (giraffe, floor) = spawn_grounded(&mut app);
+
+        locomotion(&mut app, giraffe).transition(LocomotionState::Grounded(floor));
+        app.update();
+
+        assert!(has_floor_bundle(&app, giraffe));
+        assert!(read_events(&app, &mut reader).is_empty());
+    }
+}
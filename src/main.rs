@@ -1,13 +1,25 @@
 use arena::ArenaPlugin;
+use camera_effects::CameraEffectsPlugin;
 use bevy::prelude::*;
 use bevy::render::render_resource::Texture;
 use bevy_editor_pls::prelude::*;
 use bevy_rapier2d::prelude::*;
-use circular::angular_velocity_system;
-use circular::AngularPlugin;
+use crumbling_platform::CrumblingPlatformPlugin;
 use cursor::CursorWorldPosPlugin;
+use difficulty::DifficultyPlugin;
+use game_state::GameStatePlugin;
+use hazard::HazardPlugin;
+use hud::HudPlugin;
+use locomotion::LocomotionPlugin;
+use menu::MenuPlugin;
+use moving_platform::MovingPlatformPlugin;
+use platform_catalogue::PlatformCataloguePlugin;
+use platform_pool::PlatformPoolPlugin;
 use platform_spawner::PlatformSpawnerPlugin;
-use shooting_head::ShootingHeadSystem;
+use ron_asset::RonAssetPlugin;
+use rope::RopePlugin;
+use score::ScorePlugin;
+use shooting_head::ShootingHeadPlugin;
 
 mod arena;
 mod on_floor;
@@ -15,18 +27,20 @@ mod on_floor;
 mod platform_spawner;
 
 mod camera;
+mod camera_effects;
+
+mod parallax;
 
-// mod parallax;
-//
 mod cursor;
 
-mod circular;
+mod rope;
 
 use crate::giraffe::*;
 use crate::in_air::*;
 use crate::on_floor::*;
 use bevy_kira_audio::prelude::*;
 use neck::{NeckBundle, NeckPlugin};
+use parallax::ParallaxBG;
 use platform::spawn_platform;
 
 const WINDOW_HEIGHT: f32 = 900.;
@@ -41,8 +55,22 @@ mod shooting_head;
 mod giraffe;
 mod head;
 mod in_air;
+mod locomotion;
 
+mod crumbling_platform;
+mod layout;
+mod moving_platform;
 mod platform;
+mod platform_catalogue;
+mod platform_pool;
+
+mod difficulty;
+mod game_state;
+mod hazard;
+mod hud;
+mod menu;
+mod ron_asset;
+mod score;
 
 use crate::camera::CameraPlugin;
 use crate::giraffe::*;
@@ -59,33 +87,53 @@ fn load_extra_assets(mut commands: Commands, server: Res<AssetServer>) {
 
 fn main() {
     App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            window: WindowDescriptor {
-                width: WINDOW_HEIGHT * WINDOW_WIDTH_PER_HEIGHT,
-                height: WINDOW_HEIGHT,
-                position: WindowPosition::Centered,
-                resizable: false,
-                ..default()
-            },
-            ..default()
-        }))
+        .add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    window: WindowDescriptor {
+                        width: WINDOW_HEIGHT * WINDOW_WIDTH_PER_HEIGHT,
+                        height: WINDOW_HEIGHT,
+                        position: WindowPosition::Centered,
+                        resizable: false,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .set(AssetPlugin {
+                    // Data files like difficulty.ron are reloaded while playing
+                    watch_for_changes: true,
+                    ..default()
+                }),
+        )
+        .add_plugin(RonAssetPlugin)
+        .add_plugin(DifficultyPlugin)
         .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
             PIXELS_PER_METER,
         ))
         .add_plugin(RapierDebugRenderPlugin::default())
         .add_startup_system(load_extra_assets)
+        .add_plugin(GameStatePlugin)
+        .add_plugin(MenuPlugin)
         .add_plugin(ArenaPlugin)
         .add_plugin(AudioPlugin)
         .add_plugin(CameraPlugin)
+        .add_plugin(CameraEffectsPlugin)
+        .add_plugin(ParallaxBG)
         .add_plugin(NeckPlugin)
         // .add_startup_system(spawn_platform)
         .add_plugin(EditorPlugin)
         .add_plugin(GiraffePlugin)
-        .add_plugin(InAirPlugin)
-        .add_plugin(OnFloorPlugin)
+        .add_plugin(LocomotionPlugin)
         .add_plugin(CursorWorldPosPlugin)
-        .add_system(ShootingHeadSystem)
-        .add_plugin(AngularPlugin)
+        .add_plugin(ShootingHeadPlugin)
+        .add_plugin(RopePlugin)
+        .add_plugin(PlatformCataloguePlugin)
         .add_plugin(PlatformSpawnerPlugin)
+        .add_plugin(PlatformPoolPlugin)
+        .add_plugin(MovingPlatformPlugin)
+        .add_plugin(CrumblingPlatformPlugin)
+        .add_plugin(HazardPlugin)
+        .add_plugin(ScorePlugin)
+        .add_plugin(HudPlugin)
         .run();
 }
@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+
+use crate::game_state::GameState;
+use crate::platform_spawner::PlatformSeed;
+
+const TITLE_FONT_SIZE: f32 = 64.0;
+const HINT_FONT_SIZE: f32 = 28.0;
+
+pub struct MenuPlugin;
+
+#[derive(Component)]
+struct MenuScreen;
+
+#[derive(Component)]
+struct SeedText;
+
+fn spawn_screen(commands: &mut Commands, server: &AssetServer, title: &str, hint: &str) {
+    let font = server.load("fonts/DejaVuSans-Bold.ttf");
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            MenuScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font: font.clone(),
+                    font_size: TITLE_FONT_SIZE,
+                    color: Color::WHITE,
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                hint,
+                TextStyle {
+                    font,
+                    font_size: HINT_FONT_SIZE,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+fn despawn_screen(query: Query<Entity, With<MenuScreen>>, mut commands: Commands) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn spawn_main_menu(mut commands: Commands, server: Res<AssetServer>) {
+    spawn_screen(
+        &mut commands,
+        &server,
+        "Giraffe",
+        "Space - play\nUp/Down - change seed, N - random seed, D - daily seed",
+    );
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: server.load("fonts/DejaVuSans-Bold.ttf"),
+                font_size: HINT_FONT_SIZE,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(40.0),
+                left: Val::Px(70.0),
+                ..default()
+            },
+            ..default()
+        }),
+        MenuScreen,
+        SeedText,
+    ));
+}
+
+fn spawn_pause_screen(mut commands: Commands, server: Res<AssetServer>) {
+    spawn_screen(&mut commands, &server, "Paused", "Esc - resume, M - main menu");
+}
+
+fn spawn_game_over_screen(mut commands: Commands, server: Res<AssetServer>) {
+    spawn_screen(&mut commands, &server, "Game over", "R - restart, M - main menu");
+}
+
+fn main_menu_input(
+    keys: Res<Input<KeyCode>>,
+    mut state: ResMut<State<GameState>>,
+    mut seed: ResMut<PlatformSeed>,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        let _ = state.set(GameState::Playing);
+    }
+
+    if keys.just_pressed(KeyCode::Up) {
+        seed.0 = seed.0.wrapping_add(1);
+    } else if keys.just_pressed(KeyCode::Down) {
+        seed.0 = seed.0.wrapping_sub(1);
+    } else if keys.just_pressed(KeyCode::N) {
+        *seed = PlatformSeed::random();
+    } else if keys.just_pressed(KeyCode::D) {
+        *seed = PlatformSeed::daily();
+    }
+}
+
+fn update_seed_text(seed: Res<PlatformSeed>, mut query: Query<&mut Text, With<SeedText>>) {
+    for mut text in query.iter_mut() {
+        text.sections[0].value = format!("Seed: {}", seed.0);
+    }
+}
+
+fn pause_screen_input(mut keys: ResMut<Input<KeyCode>>, mut state: ResMut<State<GameState>>) {
+    if keys.just_pressed(KeyCode::Escape) {
+        keys.reset(KeyCode::Escape);
+        let _ = state.pop();
+    } else if keys.just_pressed(KeyCode::M) {
+        let _ = state.replace(GameState::MainMenu);
+    }
+}
+
+fn game_over_input(keys: Res<Input<KeyCode>>, mut state: ResMut<State<GameState>>) {
+    if keys.just_pressed(KeyCode::R) {
+        let _ = state.set(GameState::Playing);
+    } else if keys.just_pressed(KeyCode::M) {
+        let _ = state.set(GameState::MainMenu);
+    }
+}
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(spawn_main_menu))
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu)
+                    .with_system(main_menu_input)
+                    .with_system(update_seed_text.after(main_menu_input)),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::MainMenu).with_system(despawn_screen))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Paused).with_system(spawn_pause_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Paused).with_system(pause_screen_input),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(despawn_screen))
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(spawn_game_over_screen),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver).with_system(game_over_input),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(despawn_screen));
+    }
+}
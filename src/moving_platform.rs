@@ -0,0 +1,265 @@
+use bevy::math::Rect;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::game_state::GameState;
+use crate::giraffe::giraffe_movement;
+use crate::on_floor::OnFloor;
+use crate::platform::PlatformKind;
+
+/// Length of a ping-pong path, circles and waypoint squares are half as wide
+const MOVE_EXTENT: f32 = 300.0;
+const MOVE_SPEED: f32 = 100.0;
+const CIRCLE_ANGULAR_SPEED: f32 = 1.0;
+
+pub struct MovingPlatformPlugin;
+
+/// Path a moving platform follows, positions are in world space
+#[derive(Clone, Debug)]
+pub enum PlatformPath {
+    /// Back and forth between two points
+    PingPong { from: Vec2, to: Vec2, speed: f32 },
+    /// Around a circle, counterclockwise for positive speed
+    Circle {
+        center: Vec2,
+        radius: f32,
+        angular_speed: f32,
+    },
+    /// Through the points in order, looping back from the last one to the first
+    Waypoints { points: Vec<Vec2>, speed: f32 },
+}
+
+impl PlatformPath {
+    /// Path of a platform of the given kind, starting at `start`
+    pub fn for_kind(kind: PlatformKind, start: Vec2) -> Option<Self> {
+        match kind {
+            PlatformKind::PingPong => Some(PlatformPath::PingPong {
+                from: start,
+                to: start + Vec2::X * MOVE_EXTENT,
+                speed: MOVE_SPEED,
+            }),
+            // The circle starts at angle zero, on the right of its center
+            PlatformKind::Circling => Some(PlatformPath::Circle {
+                center: start - Vec2::X * MOVE_EXTENT / 4.0,
+                radius: MOVE_EXTENT / 4.0,
+                angular_speed: CIRCLE_ANGULAR_SPEED,
+            }),
+            PlatformKind::Waypoints => Some(PlatformPath::Waypoints {
+                points: [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]
+                    .into_iter()
+                    .map(|(x, y)| start + Vec2::new(x, y) * MOVE_EXTENT / 2.0)
+                    .collect(),
+                speed: MOVE_SPEED,
+            }),
+            PlatformKind::Static | PlatformKind::Crumbling | PlatformKind::Spinning => None,
+        }
+    }
+
+    /// Smallest rectangle containing every position on the path
+    pub fn bounds(&self) -> Rect {
+        match self {
+            PlatformPath::PingPong { from, to, .. } => Rect::from_corners(*from, *to),
+            PlatformPath::Circle { center, radius, .. } => {
+                Rect::from_center_half_size(*center, Vec2::splat(radius.abs()))
+            }
+            PlatformPath::Waypoints { points, .. } => {
+                let first = points.first().copied().unwrap_or(Vec2::ZERO);
+                points.iter().fold(Rect::from_corners(first, first), |bounds, point| Rect {
+                    min: bounds.min.min(*point),
+                    max: bounds.max.max(*point),
+                })
+            }
+        }
+    }
+
+    pub fn position_at(&self, time: f32) -> Vec2 {
+        match self {
+            PlatformPath::PingPong { from, to, speed } => {
+                let length = from.distance(*to);
+                if length <= 0.0 {
+                    return *from;
+                }
+                let travelled = (time * speed).rem_euclid(2.0 * length);
+                let along = if travelled < length {
+                    travelled
+                } else {
+                    2.0 * length - travelled
+                };
+                from.lerp(*to, along / length)
+            }
+            PlatformPath::Circle {
+                center,
+                radius,
+                angular_speed,
+            } => {
+                let angle = time * angular_speed;
+                *center + Vec2::new(angle.cos(), angle.sin()) * *radius
+            }
+            PlatformPath::Waypoints { points, speed } => {
+                let segments = || points.iter().zip(points.iter().cycle().skip(1));
+                let length: f32 = segments().map(|(a, b)| a.distance(*b)).sum();
+                if length <= 0.0 {
+                    return points.first().copied().unwrap_or(Vec2::ZERO);
+                }
+
+                let mut travelled = (time * speed).rem_euclid(length);
+                for (a, b) in segments() {
+                    let segment = a.distance(*b);
+                    if travelled <= segment && segment > 0.0 {
+                        return a.lerp(*b, travelled / segment);
+                    }
+                    travelled -= segment;
+                }
+                points[0]
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MovingPlatform {
+    path: PlatformPath,
+    time: f32,
+    /// How far the platform moved during the last frame
+    delta: Vec2,
+}
+
+#[derive(Bundle)]
+pub struct MovingPlatformBundle {
+    moving: MovingPlatform,
+    rigidbody: RigidBody,
+}
+
+impl MovingPlatformBundle {
+    pub fn new(path: PlatformPath) -> Self {
+        MovingPlatformBundle {
+            moving: MovingPlatform {
+                path,
+                time: 0.0,
+                delta: Vec2::ZERO,
+            },
+            rigidbody: RigidBody::KinematicPositionBased,
+        }
+    }
+}
+
+/// Platform turning around its center
+#[derive(Component)]
+pub struct SpinningPlatform {
+    /// Radians per second, counterclockwise for positive speed
+    angular_speed: f32,
+    /// How far the platform turned during the last frame
+    delta_angle: f32,
+}
+
+#[derive(Bundle)]
+pub struct SpinningPlatformBundle {
+    spinning: SpinningPlatform,
+    rigidbody: RigidBody,
+}
+
+impl SpinningPlatformBundle {
+    pub fn new(angular_speed: f32) -> Self {
+        SpinningPlatformBundle {
+            spinning: SpinningPlatform {
+                angular_speed,
+                delta_angle: 0.0,
+            },
+            rigidbody: RigidBody::KinematicPositionBased,
+        }
+    }
+}
+
+fn move_platforms(mut query: Query<(&mut MovingPlatform, &mut Transform)>, time: Res<Time>) {
+    for (mut platform, mut transform) in query.iter_mut() {
+        let previous = platform.path.position_at(platform.time);
+        platform.time += time.delta_seconds();
+        let current = platform.path.position_at(platform.time);
+
+        platform.delta = current - previous;
+        transform.translation = current.extend(transform.translation.z);
+    }
+}
+
+fn spin_platforms(mut query: Query<(&mut SpinningPlatform, &mut Transform)>, time: Res<Time>) {
+    for (mut platform, mut transform) in query.iter_mut() {
+        platform.delta_angle = platform.angular_speed * time.delta_seconds();
+        transform.rotate_z(platform.delta_angle);
+    }
+}
+
+/// Moves a giraffe standing on a moving or spinning platform together with it
+fn carry_grounded_giraffe(
+    mut query: Query<(&mut KinematicCharacterController, &Transform, &OnFloor)>,
+    platforms: Query<(&Transform, Option<&MovingPlatform>, Option<&SpinningPlatform>)>,
+) {
+    for (mut kcc, transform, on_floor) in query.iter_mut() {
+        if let Ok((platform_transform, moving, spinning)) = platforms.get(on_floor.on_which_floor) {
+            let mut carry = Vec2::ZERO;
+
+            if let Some(moving) = moving {
+                carry += moving.delta;
+            }
+            if let Some(spinning) = spinning {
+                let offset = (transform.translation - platform_transform.translation).truncate();
+                carry += Vec2::from_angle(spinning.delta_angle).rotate(offset) - offset;
+            }
+
+            kcc.translation = Some(kcc.translation.unwrap_or(Vec2::ZERO) + carry);
+        }
+    }
+}
+
+impl Plugin for MovingPlatformPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(move_platforms)
+                .with_system(spin_platforms)
+                .with_system(
+                    carry_grounded_giraffe
+                        .after(giraffe_movement)
+                        .after(move_platforms)
+                        .after(spin_platforms),
+                ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [PlatformKind; 3] = [
+        PlatformKind::PingPong,
+        PlatformKind::Circling,
+        PlatformKind::Waypoints,
+    ];
+
+    #[test]
+    fn paths_start_at_the_spawn_position() {
+        let start = Vec2::new(120.0, -40.0);
+        for kind in KINDS {
+            let path = PlatformPath::for_kind(kind, start).unwrap();
+            assert!(path.position_at(0.0).distance(start) < 1e-3, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn paths_stay_within_bounds() {
+        for kind in KINDS {
+            let path = PlatformPath::for_kind(kind, Vec2::ZERO).unwrap();
+            let bounds = path.bounds();
+            for step in 0..1000 {
+                let pos = path.position_at(step as f32 * 0.05);
+                assert!(
+                    pos.cmpge(bounds.min - 1e-3).all() && pos.cmple(bounds.max + 1e-3).all(),
+                    "{:?} at {} is outside {:?}",
+                    kind,
+                    pos,
+                    bounds
+                );
+            }
+        }
+    }
+}
@@ -1,11 +1,18 @@
 use bevy::{prelude::*, render::render_resource::PrimitiveTopology, sprite::Mesh2dHandle};
+use bevy_rapier2d::rapier::math::{Isometry, Point, Real};
+use bevy_rapier2d::rapier::parry::shape::{Shape, TypedShape};
 use bevy_rapier2d::{prelude::*, rapier::prelude::Group};
 
+use crate::game_state::GameState;
 use crate::platform::PLATFORM_GROUP;
 
 pub const NECK_GROUP: Group = Group::GROUP_30;
 
 const NECK_WIDTH: f32 = 15.0;
+/// Points a full circle of a round collider part is approximated with
+const ROUND_SEGMENTS: usize = 16;
+/// The bend ray starts this far from the anchor, so it doesn't hit the platform it's bent around
+const BEND_RAY_OFFSET: f32 = 10.0;
 
 pub struct NeckPlugin;
 
@@ -26,6 +33,8 @@ impl NeckBundle {
             neck: Neck,
             neckpoints: NeckPoints {
                 points: vec![head_point],
+                holds: vec![None],
+                sides: Vec::new(),
                 last_point: body_point,
             },
             // collider: default(),
@@ -75,15 +84,78 @@ fn update_collision(mut query: Query<(&mut Collider, &NeckPoints)>) {
 #[derive(Component)]
 pub struct NeckTarget;
 
+/// Platform a neck point is stuck to, with the point in the platform's local space
+#[derive(Clone, Copy)]
+pub struct NeckHold {
+    pub entity: Entity,
+    pub local: Vec2,
+}
+
 #[derive(Component)]
 pub struct NeckPoints {
     pub points: Vec<Vec2>,
+    /// Platform every point of `points` moves with
+    pub holds: Vec<Option<NeckHold>>,
+    /// Winding side of every bend, `sides[i]` belongs to `points[i + 1]`
+    pub sides: Vec<f32>,
     pub last_point: Vec2,
 }
 
 impl NeckPoints {
-    pub fn add_point(&mut self, point: Vec2) {
+    pub fn add_point(&mut self, point: Vec2, hold: Option<NeckHold>) {
+        let anchor = self.anchor();
+        if anchor.distance(point) < f32::EPSILON {
+            return;
+        }
+
+        self.sides.push(Self::winding(anchor, point, self.last_point));
         self.points.push(point);
+        self.holds.push(hold);
+    }
+
+    /// Straightens the neck so it only goes from `point` to the body
+    pub fn reset(&mut self, point: Vec2, hold: Option<NeckHold>) {
+        self.points = vec![point];
+        self.holds = vec![hold];
+        self.sides.clear();
+    }
+
+    /// Point the neck is currently swinging around
+    pub fn anchor(&self) -> Vec2 {
+        self.points[self.points.len() - 1]
+    }
+
+    /// Length of the neck already wrapped around bend points
+    pub fn wrapped_length(&self) -> f32 {
+        self.points.windows(2).map(|v| v[0].distance(v[1])).sum()
+    }
+
+    /// Length of the whole neck, from the head through every bend to the body
+    pub fn length(&self) -> f32 {
+        self.wrapped_length() + self.anchor().distance(self.last_point)
+    }
+
+    /// Pops bend points whose winding side flipped, returns true if any was removed
+    pub fn unwrap_points(&mut self) -> bool {
+        let mut unwrapped = false;
+        while self.points.len() > 1 {
+            let len = self.points.len();
+            let side = Self::winding(self.points[len - 2], self.points[len - 1], self.last_point);
+
+            if side == self.sides[len - 2] {
+                break;
+            }
+
+            self.points.pop();
+            self.holds.pop();
+            self.sides.pop();
+            unwrapped = true;
+        }
+        unwrapped
+    }
+
+    fn winding(prev: Vec2, bend: Vec2, body: Vec2) -> f32 {
+        (bend - prev).perp_dot(body - bend).signum()
     }
 
     fn perp(v_a: Vec2, v_b: Vec2) -> Vec2 {
@@ -171,45 +243,120 @@ pub struct NeckBendingPoints {
 
 impl NeckBendingPoints {
     pub fn closest_point(&self, target_point: Vec2) -> Option<Vec2> {
-        let mut closest_point = None;
-        for point in &self.transformed_points {
-            if let Some(close_point) = closest_point {
+        self.closest_index(target_point).map(|i| self.transformed_points[i])
+    }
+
+    pub fn closest_index(&self, target_point: Vec2) -> Option<usize> {
+        let mut closest_index: Option<usize> = None;
+        for (i, point) in self.transformed_points.iter().enumerate() {
+            if let Some(close_index) = closest_index {
+                let close_point = self.transformed_points[close_index];
                 if target_point.distance(*point) < target_point.distance(close_point) {
-                    closest_point = Some(*point);
+                    closest_index = Some(i);
                 }
             } else {
-                closest_point = Some(*point);
+                closest_index = Some(i);
             }
         }
-        closest_point
+        closest_index
     }
 
-    pub fn from_rectangle(hxhy: Vec2) -> Self {
+    /// Corners of the collider's convex hull, clockwise in the collider's local space
+    ///
+    /// Round parts are approximated with points on their circles. Unbounded shapes like
+    /// half-spaces have no corners and give no points.
+    pub fn from_collider(collider: &Collider) -> Self {
+        let mut points = Vec::new();
+        shape_points(&*collider.raw, &Isometry::identity(), &mut points);
+
         NeckBendingPoints {
-            points: vec![
-                Vec2 {
-                    x: -hxhy.x / 2.0,
-                    y: hxhy.y / 2.0,
-                },
-                Vec2 {
-                    x: hxhy.x / 2.0,
-                    y: hxhy.y / 2.0,
-                },
-                Vec2 {
-                    x: hxhy.x / 2.0,
-                    y: -hxhy.y / 2.0,
-                },
-                Vec2 {
-                    x: -hxhy.x / 2.0,
-                    y: -hxhy.y / 2.0,
-                },
-            ],
+            points: convex_hull(points),
             transformed_points: Vec::new(),
         }
     }
 }
 
-fn transform_bending_points(mut query: Query<(&Transform, &mut NeckBendingPoints)>) {
+fn shape_points(shape: &dyn Shape, isometry: &Isometry<Real>, out: &mut Vec<Vec2>) {
+    let round = |points: &[Point<Real>], radius: Real| -> Vec<(Point<Real>, Real)> {
+        points.iter().map(|point| (*point, radius)).collect()
+    };
+    let sharp = |points: &[Point<Real>]| round(points, 0.0);
+
+    let corners = match shape.as_typed_shape() {
+        TypedShape::Ball(ball) => vec![(Point::origin(), ball.radius)],
+        TypedShape::Cuboid(cuboid) => sharp(&cuboid.to_polyline()),
+        TypedShape::RoundCuboid(cuboid) => {
+            round(&cuboid.inner_shape.to_polyline(), cuboid.border_radius)
+        }
+        TypedShape::Capsule(capsule) => {
+            round(&[capsule.segment.a, capsule.segment.b], capsule.radius)
+        }
+        TypedShape::Segment(segment) => sharp(&[segment.a, segment.b]),
+        TypedShape::Triangle(triangle) => sharp(triangle.vertices()),
+        TypedShape::RoundTriangle(triangle) => {
+            round(triangle.inner_shape.vertices(), triangle.border_radius)
+        }
+        TypedShape::ConvexPolygon(polygon) => sharp(polygon.points()),
+        TypedShape::RoundConvexPolygon(polygon) => {
+            round(polygon.inner_shape.points(), polygon.border_radius)
+        }
+        TypedShape::Polyline(polyline) => sharp(polyline.vertices()),
+        TypedShape::TriMesh(mesh) => sharp(mesh.vertices()),
+        TypedShape::Compound(compound) => {
+            for (part_isometry, part) in compound.shapes() {
+                shape_points(&**part, &(isometry * part_isometry), out);
+            }
+            return;
+        }
+        _ => return,
+    };
+
+    for (point, radius) in corners {
+        let center = isometry * point;
+        let center = Vec2::new(center.x, center.y);
+
+        if radius <= 0.0 {
+            out.push(center);
+            continue;
+        }
+        out.extend((0..ROUND_SEGMENTS).map(|i| {
+            let angle = i as f32 / ROUND_SEGMENTS as f32 * std::f32::consts::TAU;
+            center + Vec2::new(angle.cos(), angle.sin()) * radius
+        }));
+    }
+}
+
+/// Convex hull of the points, clockwise and without collinear points
+fn convex_hull(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup_by(|a, b| a.distance_squared(*b) <= f32::EPSILON);
+    if points.len() < 3 {
+        return points;
+    }
+
+    // Monotone chain keeping only right turns, first along the top then back along the bottom
+    let half_hull = |points: &mut dyn Iterator<Item = &Vec2>| {
+        let mut hull: Vec<Vec2> = Vec::new();
+        for point in points {
+            while let [.., a, b] = hull[..] {
+                if (b - a).perp_dot(*point - b) < 0.0 {
+                    break;
+                }
+                hull.pop();
+            }
+            hull.push(*point);
+        }
+        hull.pop();
+        hull
+    };
+
+    let mut hull = half_hull(&mut points.iter());
+    hull.extend(half_hull(&mut points.iter().rev()));
+    hull
+}
+
+/// Runs right after each physics step, so the points match where the colliders just were
+fn transform_bending_points(mut query: Query<(&GlobalTransform, &mut NeckBendingPoints)>) {
     for (trans, mut points) in query.iter_mut() {
         points.transformed_points = points
             .points
@@ -225,12 +372,20 @@ fn neck_bend_system(
     rapier_ctx: Res<RapierContext>,
 ) {
     for mut neck in neck_query.iter_mut() {
-        let ray_start = neck.points[neck.points.len() - 1];
+        if neck.unwrap_points() {
+            continue;
+        }
+
+        let ray_start = neck.anchor();
         let ray_end = neck.last_point - ray_start;
-        let ray_dir = ray_end.normalize();
-        let max_toi = ray_end.x / ray_dir.x;
+        let length = ray_end.length();
+        if length <= BEND_RAY_OFFSET {
+            continue;
+        }
+        let ray_dir = ray_end / length;
+        let max_toi = length - BEND_RAY_OFFSET;
 
-        let ray_pos = ray_start + ray_dir * 10.;
+        let ray_pos = ray_start + ray_dir * BEND_RAY_OFFSET;
 
         if let Some((entity, toi)) = rapier_ctx.cast_ray(
             ray_pos,
@@ -243,10 +398,33 @@ fn neck_bend_system(
                     .with_filter(PLATFORM_GROUP),
             ),
         ) {
-            let hit_point = ray_start + ray_dir * toi;
+            let hit_point = ray_pos + ray_dir * toi;
             if let Ok(points) = points_query.get(entity) {
-                let point = points.closest_point(hit_point).unwrap();
-                neck.add_point(point);
+                if let Some(i) = points.closest_index(hit_point) {
+                    neck.add_point(
+                        points.transformed_points[i],
+                        Some(NeckHold {
+                            entity,
+                            local: points.points[i],
+                        }),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn follow_neck_holds(
+    mut neck_query: Query<&mut NeckPoints>,
+    transforms: Query<&GlobalTransform>,
+) {
+    for mut neck in neck_query.iter_mut() {
+        let neck = &mut *neck;
+        for (point, hold) in neck.points.iter_mut().zip(neck.holds.iter()) {
+            if let Some(hold) = hold {
+                if let Ok(transform) = transforms.get(hold.entity) {
+                    *point = transform.transform_point(hold.local.extend(0.0)).truncate();
+                }
             }
         }
     }
@@ -266,7 +444,7 @@ fn neck_mouse(
     for mut neck in neck_query.iter_mut() {
         neck.last_point = cursor;
         if input.just_pressed(MouseButton::Left) {
-            neck.add_point(cursor);
+            neck.add_point(cursor, None);
         }
     }
 }
@@ -315,7 +493,135 @@ impl Plugin for NeckPlugin {
             .add_system(add_mesh)
             // .add_system(neck_mouse)
             .add_system(update_collision)
-            .add_system(neck_bend_system)
-            .add_system(transform_bending_points);
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(neck_bend_system),
+            )
+            // Rotating and moving platforms drag the neck along every physics step
+            .add_system_to_stage(PhysicsStages::Writeback, transform_bending_points)
+            .add_system_to_stage(PhysicsStages::Writeback, follow_neck_holds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_4, SQRT_2};
+
+    use super::*;
+
+    fn hull_of(collider: &Collider) -> Vec<Vec2> {
+        NeckBendingPoints::from_collider(collider).points
+    }
+
+    /// Twice the signed area, negative for clockwise polygons
+    fn signed_area(points: &[Vec2]) -> f32 {
+        points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| a.perp_dot(*b))
+            .sum()
+    }
+
+    fn assert_clockwise(points: &[Vec2]) {
+        assert!(signed_area(points) < 0.0, "{:?} isn't clockwise", points);
+    }
+
+    fn assert_same_points(actual: &[Vec2], expected: &[Vec2]) {
+        assert_eq!(actual.len(), expected.len(), "{:?} != {:?}", actual, expected);
+        for point in expected {
+            assert!(
+                actual.iter().any(|p| p.distance(*point) < 1e-4),
+                "{:?} is missing from {:?}",
+                point,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn cuboid_gives_its_corners() {
+        let points = hull_of(&Collider::cuboid(2.0, 1.0));
+
+        assert_same_points(
+            &points,
+            &[
+                Vec2::new(-2.0, 1.0),
+                Vec2::new(2.0, 1.0),
+                Vec2::new(2.0, -1.0),
+                Vec2::new(-2.0, -1.0),
+            ],
+        );
+        assert_clockwise(&points);
+    }
+
+    #[test]
+    fn compound_parts_are_moved_and_rotated() {
+        let collider = Collider::compound(vec![
+            (Vec2::new(-3.0, 0.0), 0.0, Collider::cuboid(1.0, 1.0)),
+            (Vec2::new(3.0, 0.0), FRAC_PI_4, Collider::cuboid(1.0, 1.0)),
+        ]);
+        let points = hull_of(&collider);
+
+        // The inner corners of the left square end up inside the hull
+        assert_same_points(
+            &points,
+            &[
+                Vec2::new(-4.0, 1.0),
+                Vec2::new(3.0, SQRT_2),
+                Vec2::new(3.0 + SQRT_2, 0.0),
+                Vec2::new(3.0, -SQRT_2),
+                Vec2::new(-4.0, -1.0),
+            ],
+        );
+        assert_clockwise(&points);
+    }
+
+    #[test]
+    fn collinear_points_are_dropped() {
+        let points = convex_hull(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(1.0, 1.0),
+        ]);
+
+        assert_same_points(
+            &points,
+            &[
+                Vec2::new(0.0, 0.0),
+                Vec2::new(2.0, 0.0),
+                Vec2::new(2.0, 2.0),
+                Vec2::new(0.0, 2.0),
+            ],
+        );
+        assert_clockwise(&points);
+    }
+
+    #[test]
+    fn ball_is_approximated_with_round_segments() {
+        let points = hull_of(&Collider::ball(3.0));
+
+        assert_eq!(points.len(), ROUND_SEGMENTS);
+        assert!(points.iter().all(|p| (p.length() - 3.0).abs() < 1e-4));
+        assert_clockwise(&points);
+    }
+
+    #[test]
+    fn capsule_points_lie_on_its_border() {
+        let points = hull_of(&Collider::capsule_y(2.0, 1.0));
+
+        assert!(points.len() > ROUND_SEGMENTS);
+        for point in &points {
+            let closest = Vec2::new(0.0, point.y.clamp(-2.0, 2.0));
+            assert!((point.distance(closest) - 1.0).abs() < 1e-4, "{:?}", point);
+        }
+        assert_clockwise(&points);
+    }
+
+    #[test]
+    fn half_space_has_no_points() {
+        let collider = Collider::halfspace(Vec2::Y).unwrap();
+        assert!(hull_of(&collider).is_empty());
     }
 }
@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// Axes a layer's texture is repeated along
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum LayerRepeat {
+    #[default]
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+impl LayerRepeat {
+    pub fn repeats_x(self) -> bool {
+        matches!(self, LayerRepeat::Horizontal | LayerRepeat::Both)
+    }
+
+    pub fn repeats_y(self) -> bool {
+        matches!(self, LayerRepeat::Vertical | LayerRepeat::Both)
+    }
+}
+
+/// Parallax layer, the parent of its texture tiles
+#[derive(Component)]
+pub struct LayerComponent {
+    /// Relative speed of layer to the camera movement on each axis
+    pub speed: Vec2,
+    pub repeat: LayerRepeat,
+    /// Number of textures in the layer along each axis
+    pub texture_count: Vec2,
+    /// Part of the layer's length a texture can get from the camera before it's moved to the
+    /// opposite side
+    pub transition_factor: f32,
+}
+
+/// One tile of a parallax layer's texture
+#[derive(Component)]
+pub struct LayerTextureComponent {
+    /// Size of the tile in the layer's units
+    pub size: Vec2,
+}
@@ -1,9 +1,23 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
-#[derive(Debug)]
+use crate::camera::{camera_movement_system, MainCamera};
+use crate::ron_asset::{updated_source, RonSource};
+
+pub mod layer;
+
+use layer::LayerRepeat;
+
+const PARALLAX_FILE: &str = "parallax.ron";
+/// Background layers are drawn below everything else, but inside the 2D camera's near plane
+const BACKGROUND_Z: f32 = -0.09;
+/// Wrapping tiles at half a layer from the camera keeps them there once moved
+const DEFAULT_TRANSITION_FACTOR: f32 = 0.5;
+
+#[derive(Debug, Deserialize)]
 pub struct LayerData {
-    /// Relative speed of layer to the camera movement
-    pub speed: f32,
+    /// Relative speed of layer to the camera movement on each axis
+    pub speed: Vec2,
     /// Path to layer texture file
     pub path: String,
     /// Size of a tile of the texture
@@ -17,14 +31,24 @@ pub struct LayerData {
     /// Z position of the layer
     pub z: f32,
     /// Default initial position of the Entity container
+    #[serde(default)]
     pub position: Vec2,
-    /// Number used to determine when textures are moved to opposite side of camera
+    /// Part of the layer's length a texture can get from the camera before it's moved to the
+    /// opposite side, below 0.5 moved textures end up past the limit again
+    #[serde(default = "default_transition_factor")]
     pub transition_factor: f32,
+    /// Axes the texture is tiled along, a layer should follow the camera fully on the others
+    #[serde(default)]
+    pub repeat: LayerRepeat,
+}
+
+fn default_transition_factor() -> f32 {
+    DEFAULT_TRANSITION_FACTOR
 }
 
 pub struct ParallaxBG;
 
-#[derive(Debug)]
+#[derive(Resource, Debug)]
 pub struct ParallaxResource {
     /// Data to describe each layer of parallax
     pub layer_data: Vec<LayerData>,
@@ -44,6 +68,17 @@ impl Default for ParallaxResource {
     }
 }
 
+/// Number of tiles on each side of the center one needed to cover two windows
+fn tiles_per_side(tile: f32, scale: f32, window: f32) -> i32 {
+    let mut count = 1;
+    let mut max = tile / 2.0 * scale + tile * scale;
+    while max < window {
+        max += tile * scale;
+        count += 1;
+    }
+    count
+}
+
 impl ParallaxResource {
     /// Create a new parallax resource
     pub fn new(layer_data: Vec<LayerData>) -> Self {
@@ -65,12 +100,13 @@ impl ParallaxResource {
         self.layer_entities = vec![];
     }
 
-    /// Create layers from layer data
+    /// Create layers from layer data, placed as if they followed the camera to `camera_pos`
     pub fn create_layers(
         &mut self,
         commands: &mut Commands,
         asset_server: &AssetServer,
         texture_atlases: &mut Assets<TextureAtlas>,
+        camera_pos: Vec2,
     ) {
         // Despawn any existing layers
         self.despawn_layers(commands);
@@ -78,89 +114,71 @@ impl ParallaxResource {
         // Spawn new layers using layer_data
         for (i, layer) in self.layer_data.iter().enumerate() {
             // Setup texture
-            let texture_handle = asset_server.load(&layer.path);
-            let texture_atlas =
-                TextureAtlas::from_grid(texture_handle, layer.tile_size, layer.cols, layer.rows, None, None);
+            let texture_handle = asset_server.load(layer.path.as_str());
+            let texture_atlas = TextureAtlas::from_grid(
+                texture_handle,
+                layer.tile_size,
+                layer.cols,
+                layer.rows,
+                None,
+                None,
+            );
             let texture_atlas_handle = texture_atlases.add(texture_atlas);
             let spritesheet_bundle = SpriteSheetBundle {
                 texture_atlas: texture_atlas_handle,
                 ..Default::default()
             };
 
-            // Three textures always spawned
-            let mut texture_count = 3.0;
+            // Enough textures to make 2 windows length of background along each repeated axis
+            let per_side_x = if layer.repeat.repeats_x() {
+                tiles_per_side(layer.tile_size.x, layer.scale, self.window_size.x)
+            } else {
+                0
+            };
+            let per_side_y = if layer.repeat.repeats_y() {
+                tiles_per_side(layer.tile_size.y, layer.scale, self.window_size.y)
+            } else {
+                0
+            };
 
             // Spawn parallax layer entity
-            let mut entity_commands = commands.spawn();
-            entity_commands
-                .insert(Name::new(format!("Parallax Layer ({})", i)))
-                .insert_bundle(SpatialBundle {
+            let position = layer.position + camera_pos * layer.speed;
+            let mut entity_commands = commands.spawn((
+                Name::new(format!("Parallax Layer ({})", i)),
+                SpatialBundle {
                     transform: Transform {
-                        translation: Vec3::new(layer.position.x, layer.position.y, layer.z),
+                        translation: position.extend(layer.z),
                         scale: Vec3::new(layer.scale, layer.scale, 1.0),
                         ..default()
                     },
                     ..default()
-                })
-                .with_children(|parent| {
-                    // Spawn center texture
-                    parent.spawn_bundle(spritesheet_bundle.clone()).insert(
-                        layer::LayerTextureComponent {
-                            width: layer.tile_size.x,
-                        },
-                    );
-
-                    let mut max_x = (layer.tile_size.x / 2.0) * layer.scale;
-                    let mut adjusted_spritesheet_bundle = spritesheet_bundle.clone();
-
-                    // Spawn right texture
-                    adjusted_spritesheet_bundle.transform.translation.x += layer.tile_size.x;
-                    max_x += layer.tile_size.x * layer.scale;
-                    parent
-                        .spawn_bundle(adjusted_spritesheet_bundle.clone())
-                        .insert(layer::LayerTextureComponent {
-                            width: layer.tile_size.x,
-                        });
-
-                    // Spawn left texture
-                    parent
-                        .spawn_bundle({
-                            let mut bundle = adjusted_spritesheet_bundle.clone();
-                            bundle.transform.translation.x *= -1.0;
-                            bundle
-                        })
-                        .insert(layer::LayerTextureComponent {
-                            width: layer.tile_size.x,
-                        });
-
-                    // Spawn additional textures to make 2 windows length of background textures
-                    while max_x < self.window_size.x {
-                        adjusted_spritesheet_bundle.transform.translation.x += layer.tile_size.x;
-                        max_x += layer.tile_size.x * layer.scale;
-                        parent
-                            .spawn_bundle(adjusted_spritesheet_bundle.clone())
-                            .insert(layer::LayerTextureComponent {
-                                width: layer.tile_size.x,
-                            });
-
-                        parent
-                            .spawn_bundle({
-                                let mut bundle = adjusted_spritesheet_bundle.clone();
-                                bundle.transform.translation.x *= -1.0;
-                                bundle
-                            })
-                            .insert(layer::LayerTextureComponent {
-                                width: layer.tile_size.x,
-                            });
-
-                        texture_count += 2.0;
+                },
+            ));
+            entity_commands.with_children(|parent| {
+                for x in -per_side_x..=per_side_x {
+                    for y in -per_side_y..=per_side_y {
+                        let mut bundle = spritesheet_bundle.clone();
+                        bundle.transform.translation.x = x as f32 * layer.tile_size.x;
+                        bundle.transform.translation.y = y as f32 * layer.tile_size.y;
+
+                        parent.spawn((
+                            bundle,
+                            layer::LayerTextureComponent {
+                                size: layer.tile_size,
+                            },
+                        ));
                     }
-                });
+                }
+            });
 
             // Add layer component to entity
             entity_commands.insert(layer::LayerComponent {
                 speed: layer.speed,
-                texture_count,
+                repeat: layer.repeat,
+                texture_count: Vec2::new(
+                    (per_side_x * 2 + 1) as f32,
+                    (per_side_y * 2 + 1) as f32,
+                ),
                 transition_factor: layer.transition_factor,
             });
 
@@ -170,8 +188,143 @@ impl ParallaxResource {
     }
 }
 
+/// Layers made from the `assets/tlo_roz` images, from the farthest to the closest one
+fn default_layers() -> Vec<LayerData> {
+    [
+        ("tlo_roz/small/tlo.png", Vec2::new(1.0, 1.0)),
+        ("tlo_roz/small/gwiazdy_wieksze.png", Vec2::new(0.95, 0.9)),
+        ("tlo_roz/small/planety_malutkie.png", Vec2::new(0.9, 0.8)),
+        ("tlo_roz/small/mgla.png", Vec2::new(0.8, 0.6)),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, (path, speed))| LayerData {
+        speed,
+        path: path.to_string(),
+        tile_size: Vec2::new(1000.0, 5000.0),
+        cols: 1,
+        rows: 1,
+        scale: 1.0,
+        z: BACKGROUND_Z + i as f32 * 0.01,
+        position: Vec2::ZERO,
+        transition_factor: DEFAULT_TRANSITION_FACTOR,
+        repeat: LayerRepeat::Both,
+    })
+    .collect()
+}
+
+#[derive(Resource)]
+struct ParallaxFile(Handle<RonSource>);
+
+impl FromWorld for ParallaxFile {
+    fn from_world(world: &mut World) -> Self {
+        ParallaxFile(world.resource::<AssetServer>().load(PARALLAX_FILE))
+    }
+}
+
+fn initialize_parallax_system(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut parallax_res: ResMut<ParallaxResource>,
+) {
+    if let Some(window) = windows.get_primary() {
+        parallax_res.window_size = Vec2::new(window.width(), window.height());
+    }
+    parallax_res.create_layers(&mut commands, &asset_server, &mut texture_atlases, Vec2::ZERO);
+}
+
+/// Recreates the layers whenever the data file is loaded or changes, a broken file is skipped
+fn update_parallax_layers_system(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<RonSource>>,
+    file: Res<ParallaxFile>,
+    sources: Res<Assets<RonSource>>,
+    camera_query: Query<&Transform, With<MainCamera>>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut parallax_res: ResMut<ParallaxResource>,
+) {
+    if let Some(source) = updated_source(&mut events, &file.0, &sources) {
+        match ron::from_str::<Vec<LayerData>>(source) {
+            Ok(layers) => {
+                let camera_pos = camera_query
+                    .get_single()
+                    .map_or(Vec2::ZERO, |camera| camera.translation.truncate());
+
+                parallax_res.layer_data = layers;
+                parallax_res.create_layers(
+                    &mut commands,
+                    &asset_server,
+                    &mut texture_atlases,
+                    camera_pos,
+                );
+            }
+            Err(err) => warn!("Couldn't load {}: {}", PARALLAX_FILE, err),
+        }
+    }
+}
+
+/// Moves the layers along with the camera by their speed, a layer with speed 1 looks fixed on screen
+fn follow_camera_system(
+    camera_query: Query<&Transform, (With<MainCamera>, Without<layer::LayerComponent>)>,
+    mut layer_query: Query<(&mut Transform, &layer::LayerComponent)>,
+    mut last_camera_pos: Local<Option<Vec2>>,
+) {
+    if let Ok(camera) = camera_query.get_single() {
+        let camera_pos = camera.translation.truncate();
+        let camera_move = camera_pos - last_camera_pos.unwrap_or(camera_pos);
+        *last_camera_pos = Some(camera_pos);
+
+        for (mut layer_transform, layer) in layer_query.iter_mut() {
+            layer_transform.translation += (camera_move * layer.speed).extend(0.0);
+        }
+    }
+}
+
+/// Moves textures that got too far from the camera to the other side of their layer
+///
+/// The move is one layer long, so with a transition factor of at least 0.5 the texture ends up
+/// within the limit and stays there until the camera moves on.
+fn update_layer_textures_system(
+    layer_query: Query<(&layer::LayerComponent, &Children)>,
+    mut texture_query: Query<
+        (&GlobalTransform, &mut Transform, &layer::LayerTextureComponent),
+        Without<layer::LayerComponent>,
+    >,
+    camera_query: Query<&GlobalTransform, With<MainCamera>>,
+) {
+    if let Ok(camera) = camera_query.get_single() {
+        for (layer, children) in layer_query.iter() {
+            for &child in children.iter() {
+                if let Ok((global, mut transform, texture)) = texture_query.get_mut(child) {
+                    let scale = global.compute_transform().scale.truncate();
+                    let offset = (camera.translation() - global.translation()).truncate();
+                    let limit =
+                        texture.size * scale * layer.texture_count * layer.transition_factor;
+
+                    if layer.repeat.repeats_x() && offset.x.abs() > limit.x {
+                        transform.translation.x +=
+                            offset.x.signum() * texture.size.x * layer.texture_count.x;
+                    }
+                    if layer.repeat.repeats_y() && offset.y.abs() > limit.y {
+                        transform.translation.y +=
+                            offset.y.signum() * texture.size.y * layer.texture_count.y;
+                    }
+                }
+            }
+        }
+    }
+}
+
 impl Plugin for ParallaxBG {
     fn build(&self, app: &mut App) {
-        todo!()
+        app.insert_resource(ParallaxResource::new(default_layers()))
+            .init_resource::<ParallaxFile>()
+            .add_startup_system(initialize_parallax_system)
+            .add_system(update_parallax_layers_system)
+            .add_system(follow_camera_system.after(camera_movement_system))
+            .add_system(update_layer_textures_system.after(follow_camera_system));
     }
 }
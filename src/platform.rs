@@ -1,6 +1,7 @@
 use crate::neck::NeckBendingPoints;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
 
 pub const PLATFORM_GROUP: bevy_rapier2d::rapier::geometry::Group =
     bevy_rapier2d::rapier::geometry::Group::GROUP_15;
@@ -8,6 +9,86 @@ pub const PLATFORM_GROUP: bevy_rapier2d::rapier::geometry::Group =
 #[derive(Component)]
 pub struct Platform;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum PlatformKind {
+    Static,
+    /// Moves left and right
+    PingPong,
+    /// Goes around in a circle
+    Circling,
+    /// Moves along a square of waypoints
+    Waypoints,
+    /// Breaks a moment after the giraffe lands on it
+    Crumbling,
+    /// Turns around its center
+    Spinning,
+}
+
+/// Material of the platform top, changes how the giraffe moves on it
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum PlatformSurface {
+    #[default]
+    Normal,
+    /// Giraffe bounces off and can't stand on it
+    Bouncy,
+    /// Slow to speed up and slow down on, the neck slides off
+    Icy,
+    Sticky,
+}
+
+impl PlatformSurface {
+    pub fn restitution(&self) -> Restitution {
+        let coefficient = match self {
+            PlatformSurface::Bouncy => 1.0,
+            _ => 0.0,
+        };
+        Restitution {
+            coefficient,
+            combine_rule: CoefficientCombineRule::Max,
+        }
+    }
+
+    pub fn friction(&self) -> Friction {
+        match self {
+            PlatformSurface::Icy => Friction {
+                coefficient: 0.0,
+                combine_rule: CoefficientCombineRule::Min,
+            },
+            PlatformSurface::Sticky => Friction {
+                coefficient: 1.0,
+                combine_rule: CoefficientCombineRule::Max,
+            },
+            _ => Friction::default(),
+        }
+    }
+
+    /// How fast walking speed changes, in pixels per second squared
+    pub fn walk_acceleration(&self) -> f32 {
+        match self {
+            PlatformSurface::Icy => 400.0,
+            PlatformSurface::Sticky => 1500.0,
+            _ => 3000.0,
+        }
+    }
+
+    pub fn can_stand_on(&self) -> bool {
+        *self != PlatformSurface::Bouncy
+    }
+
+    pub fn can_anchor(&self) -> bool {
+        *self != PlatformSurface::Icy
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            PlatformSurface::Normal => Color::rgb(0.8, 0.8, 1.0),
+            PlatformSurface::Bouncy => Color::rgb(1.0, 0.5, 0.8),
+            PlatformSurface::Icy => Color::rgb(0.6, 0.95, 1.0),
+            PlatformSurface::Sticky => Color::rgb(0.6, 0.8, 0.3),
+        }
+    }
+}
+
 #[derive(Bundle)]
 pub struct PlatformBundle {
     platform: Platform,
@@ -15,6 +96,9 @@ pub struct PlatformBundle {
     collider: Collider,
     group: Group,
     bending_points: NeckBendingPoints,
+    surface: PlatformSurface,
+    restitution: Restitution,
+    friction: Friction,
 }
 
 impl PlatformBundle {
@@ -23,7 +107,37 @@ impl PlatformBundle {
         self
     }
 
+    pub fn with_texture(mut self, texture: Handle<Image>, rect: Rect) -> Self {
+        self.sprite.texture = texture;
+        self.sprite.sprite.rect = Some(rect);
+        self
+    }
+
+    /// Replaces the collider, the neck bends around the corners of the new one
+    pub fn with_collider(mut self, collider: Collider) -> Self {
+        self.bending_points = NeckBendingPoints::from_collider(&collider);
+        self.collider = collider;
+        self
+    }
+
+    /// Uses the convex hull of the outline as the collider
+    pub fn with_outline(self, outline: Vec<Vec2>) -> Self {
+        match Collider::convex_hull(&outline) {
+            Some(collider) => self.with_collider(collider),
+            None => self,
+        }
+    }
+
+    pub fn with_surface(mut self, surface: PlatformSurface) -> Self {
+        self.surface = surface;
+        self.sprite.sprite.color = surface.color();
+        self.restitution = surface.restitution();
+        self.friction = surface.friction();
+        self
+    }
+
     pub fn type_one(pos: Vec2, size: Vec2) -> Self {
+        let collider = Collider::cuboid(size.x / 2.0, size.y / 2.0);
         PlatformBundle {
             platform: Platform,
             sprite: SpriteBundle {
@@ -34,9 +148,12 @@ impl PlatformBundle {
                 },
                 ..default()
             },
-            collider: Collider::cuboid(size.x / 2.0, size.y / 2.0),
+            bending_points: NeckBendingPoints::from_collider(&collider),
+            collider,
             group: Group::from_bits(PLATFORM_GROUP.bits()).unwrap(),
-            bending_points: NeckBendingPoints::from_rectangle(size),
+            surface: PlatformSurface::Normal,
+            restitution: PlatformSurface::Normal.restitution(),
+            friction: PlatformSurface::Normal.friction(),
         }
         .with_start_pos(pos)
     }
@@ -0,0 +1,115 @@
+use bevy::math::Rect;
+use bevy::prelude::*;
+
+use crate::platform::PlatformBundle;
+
+pub struct PlatformCataloguePlugin;
+
+/// One platform sprite, cut out of its canvas in `assets/platformy`
+pub struct PlatformArt {
+    pub path: &'static str,
+    /// Part of the image with the platform, in image pixels
+    pub rect: Rect,
+    /// Size the platform is drawn at before scaling, in world pixels
+    pub size: Vec2,
+    /// How much the outline cuts off the corners, in image pixels
+    pub bevel: f32,
+}
+
+const fn art(
+    path: &'static str,
+    min: (f32, f32),
+    max: (f32, f32),
+    size: (f32, f32),
+    bevel: f32,
+) -> PlatformArt {
+    PlatformArt {
+        path,
+        rect: Rect {
+            min: Vec2::new(min.0, min.1),
+            max: Vec2::new(max.0, max.1),
+        },
+        size: Vec2::new(size.0, size.1),
+        bevel,
+    }
+}
+
+pub const PLATFORM_ART: [PlatformArt; 8] = [
+    art("platformy/pl_1.png", (628.0, 839.0), (939.0, 951.0), (311.0, 112.0), 6.0),
+    art("platformy/pl_2.png", (81.0, 850.0), (394.0, 964.0), (313.0, 114.0), 17.0),
+    art("platformy/pl_3.png", (615.0, 632.0), (926.0, 744.0), (311.0, 112.0), 6.0),
+    art("platformy/pl_4.png", (76.0, 624.0), (387.0, 736.0), (311.0, 112.0), 6.0),
+    art("platformy/pl_5.png", (615.0, 372.0), (926.0, 484.0), (311.0, 112.0), 6.0),
+    art("platformy/pl_6.png", (76.0, 380.0), (387.0, 492.0), (311.0, 112.0), 6.0),
+    art("platformy/pl_7.png", (628.0, 165.0), (939.0, 277.0), (311.0, 112.0), 6.0),
+    art("platformy/pl_8.png", (81.0, 152.0), (394.0, 266.0), (313.0, 114.0), 17.0),
+];
+
+/// Height of the tallest art scaled to `width`, platforms of that width fit in it
+pub fn tallest_at_width(width: f32) -> f32 {
+    PLATFORM_ART
+        .iter()
+        .map(|art| art.size_at_width(width).y)
+        .fold(0.0, f32::max)
+}
+
+impl PlatformArt {
+    /// Size of the art scaled to `width`, keeping its proportions
+    pub fn size_at_width(&self, width: f32) -> Vec2 {
+        self.size * width / self.size.x
+    }
+
+    /// Convex outline of the platform with beveled corners, clockwise from the top left
+    ///
+    /// The art is scaled to `size`, so the outline is scaled the same way.
+    pub fn outline(&self, size: Vec2) -> Vec<Vec2> {
+        let half = size / 2.0;
+        let bevel = Vec2::splat(self.bevel) * size / self.rect.size();
+        let bevel = bevel.min(half);
+
+        vec![
+            Vec2::new(-half.x + bevel.x, half.y),
+            Vec2::new(half.x - bevel.x, half.y),
+            Vec2::new(half.x, half.y - bevel.y),
+            Vec2::new(half.x, -half.y + bevel.y),
+            Vec2::new(half.x - bevel.x, -half.y),
+            Vec2::new(-half.x + bevel.x, -half.y),
+            Vec2::new(-half.x, -half.y + bevel.y),
+            Vec2::new(-half.x, half.y - bevel.y),
+        ]
+    }
+}
+
+#[derive(Resource)]
+pub struct PlatformCatalogue {
+    textures: Vec<Handle<Image>>,
+}
+
+impl FromWorld for PlatformCatalogue {
+    fn from_world(world: &mut World) -> Self {
+        let server = world.resource::<AssetServer>();
+        Self {
+            textures: PLATFORM_ART.iter().map(|art| server.load(art.path)).collect(),
+        }
+    }
+}
+
+impl PlatformCatalogue {
+    /// Platform using the art picked by `variant` scaled to `width`, with a collider and bending
+    /// points from its outline
+    pub fn platform(&self, variant: u32, pos: Vec2, width: f32) -> PlatformBundle {
+        let index = variant as usize % PLATFORM_ART.len();
+        let art = &PLATFORM_ART[index];
+        let size = art.size_at_width(width);
+
+        PlatformBundle::type_one(pos, size)
+            .with_texture(self.textures[index].clone(), art.rect)
+            .with_outline(art.outline(size))
+    }
+}
+
+impl Plugin for PlatformCataloguePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlatformCatalogue>();
+    }
+}
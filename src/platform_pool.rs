@@ -0,0 +1,248 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::arena::Floor;
+use crate::camera::MainCamera;
+use crate::crumbling_platform::CrumblingPlatform;
+use crate::game_state::{GameState, RunEntity};
+use crate::moving_platform::{MovingPlatform, SpinningPlatform};
+use crate::neck::NeckBendingPoints;
+use crate::platform::{Platform, PlatformBundle};
+
+pub struct PlatformPoolPlugin;
+
+#[derive(Resource)]
+pub struct PlatformPoolSettings {
+    /// How far below the camera a platform has to be to get recycled
+    pub recycle_distance: f32,
+    /// Most platforms kept for reuse, the rest get despawned
+    pub capacity: usize,
+}
+
+impl Default for PlatformPoolSettings {
+    fn default() -> Self {
+        Self {
+            recycle_distance: 2000.0,
+            capacity: 64,
+        }
+    }
+}
+
+/// Marks a platform entity waiting in the pool
+#[derive(Component)]
+struct Pooled;
+
+#[derive(Resource, Default)]
+pub struct PlatformPool {
+    free: Vec<Entity>,
+}
+
+impl PlatformPool {
+    /// Spawns the platform reusing a pooled entity when there is one
+    pub fn spawn(&mut self, commands: &mut Commands, platform: PlatformBundle) -> Entity {
+        if let Some(entity) = self.free.pop() {
+            commands.entity(entity).remove::<Pooled>().insert(platform);
+            entity
+        } else {
+            commands.spawn((platform, RunEntity)).id()
+        }
+    }
+}
+
+fn reset_pool(mut pool: ResMut<PlatformPool>) {
+    // Pooled entities are despawned with the rest of the previous run
+    pool.free.clear();
+}
+
+/// Frees platforms once their recycling commands ran, so a reused entity can't be stripped by
+/// commands still queued from recycling it
+///
+/// Runs outside of runs too, so the last platforms of a run are collected before the pool is
+/// reset for the next one.
+fn collect_pooled(query: Query<Entity, Added<Pooled>>, mut pool: ResMut<PlatformPool>) {
+    pool.free.extend(query.iter());
+}
+
+fn recycle_platforms(
+    mut query: Query<
+        (Entity, &GlobalTransform, &mut Visibility),
+        (With<Platform>, Without<Floor>),
+    >,
+    camera_query: Query<&GlobalTransform, With<MainCamera>>,
+    settings: Res<PlatformPoolSettings>,
+    pool: Res<PlatformPool>,
+    mut commands: Commands,
+) {
+    if let Ok(camera) = camera_query.get_single() {
+        let recycle_below = camera.translation().y - settings.recycle_distance;
+        let mut pooled = pool.free.len();
+
+        for (entity, transform, mut visibility) in query.iter_mut() {
+            if transform.translation().y > recycle_below {
+                continue;
+            }
+
+            if pooled < settings.capacity {
+                // Pooled platforms stay as hidden entities without any gameplay components
+                visibility.is_visible = false;
+                commands
+                    .entity(entity)
+                    .remove::<(Platform, Collider, NeckBendingPoints)>()
+                    .remove::<(MovingPlatform, SpinningPlatform, RigidBody, CrumblingPlatform)>()
+                    .insert(Pooled);
+                pooled += 1;
+            } else {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
+
+impl Plugin for PlatformPoolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlatformPoolSettings>()
+            .init_resource::<PlatformPool>()
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_pool))
+            .add_system(collect_pooled)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(recycle_platforms.after(collect_pooled)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::window::WindowId;
+
+    use super::*;
+    use crate::difficulty::DifficultyProfile;
+    use crate::giraffe::Giraffe;
+    use crate::platform_catalogue::PlatformCataloguePlugin;
+    use crate::platform_spawner::{PlatformSeed, PlatformSpawnerPlugin};
+
+    const WINDOW_SIZE: f32 = 900.0;
+
+    fn test_app() -> App {
+        let mut windows = Windows::default();
+        windows.add(Window::new(
+            WindowId::primary(),
+            &WindowDescriptor {
+                width: WINDOW_SIZE,
+                height: WINDOW_SIZE,
+                ..default()
+            },
+            WINDOW_SIZE as u32,
+            WINDOW_SIZE as u32,
+            1.0,
+            None,
+            None,
+        ));
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(TransformPlugin)
+            .add_state(GameState::Playing)
+            .insert_resource(windows)
+            .insert_resource(RapierConfiguration {
+                gravity: Vec2::new(0.0, -981.0),
+                ..default()
+            })
+            .insert_resource(DifficultyProfile::default())
+            .add_plugin(PlatformCataloguePlugin)
+            .add_plugin(PlatformSpawnerPlugin)
+            .add_plugin(PlatformPoolPlugin)
+            .insert_resource(PlatformSeed(1));
+
+        app.world.spawn(Giraffe::for_tests());
+        app.world.spawn((Camera::default(), TransformBundle::default(), MainCamera));
+        app
+    }
+
+    fn platform_count(app: &mut App) -> usize {
+        app.world
+            .query_filtered::<(), Or<(With<Platform>, With<Pooled>)>>()
+            .iter(&app.world)
+            .count()
+    }
+
+    #[test]
+    fn long_climb_keeps_platform_count_bounded() {
+        const CLIMB_PER_UPDATE: f32 = 50.0;
+        const UPDATES: usize = 2000;
+        const MAX_PLATFORMS: usize = 128;
+
+        let mut app = test_app();
+        let mut most = 0;
+
+        for i in 0..UPDATES {
+            let mut camera = app
+                .world
+                .query_filtered::<&mut Transform, With<MainCamera>>()
+                .single_mut(&mut app.world);
+            camera.translation.y = i as f32 * CLIMB_PER_UPDATE;
+
+            app.update();
+            most = most.max(platform_count(&mut app));
+        }
+
+        // Without recycling the climb leaves hundreds of platforms behind
+        let climbed = UPDATES as f32 * CLIMB_PER_UPDATE;
+        let spacing = DifficultyProfile::default().bands[0].spacing;
+        assert!(climbed / spacing > 2.0 * MAX_PLATFORMS as f32);
+
+        assert!(most > 0);
+        assert!(most <= MAX_PLATFORMS, "{} platforms alive", most);
+    }
+
+    /// Platforms handed out by `reuse_every_frame`
+    #[derive(Resource, Default)]
+    struct Reused(Vec<Entity>);
+
+    fn reuse_every_frame(
+        mut pool: ResMut<PlatformPool>,
+        mut reused: ResMut<Reused>,
+        mut commands: Commands,
+    ) {
+        let platform = PlatformBundle::type_one(Vec2::new(0.0, 10000.0), Vec2::new(250.0, 100.0));
+        reused.0.push(pool.spawn(&mut commands, platform));
+    }
+
+    #[test]
+    fn platform_recycled_and_reused_in_one_frame_stays_whole() {
+        let camera = Transform::from_xyz(0.0, 10000.0, 0.0);
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(TransformPlugin)
+            .add_state(GameState::Playing)
+            .add_plugin(PlatformPoolPlugin)
+            .init_resource::<Reused>()
+            .add_system(reuse_every_frame.after(recycle_platforms));
+
+        app.world.spawn((camera, GlobalTransform::from(camera), MainCamera));
+        let recycled = app
+            .world
+            .spawn(PlatformBundle::type_one(Vec2::ZERO, Vec2::new(250.0, 100.0)))
+            .id();
+
+        for _ in 0..5 {
+            app.update();
+        }
+
+        let reused = &app.world.resource::<Reused>().0;
+        assert!(reused.contains(&recycled), "the recycled platform was never reused");
+        for &entity in reused {
+            let entity = app.world.entity(entity);
+            assert!(entity.contains::<Platform>() && entity.contains::<Collider>());
+            assert!(!entity.contains::<Pooled>());
+        }
+        for &entity in &app.world.resource::<PlatformPool>().free {
+            let entity = app.world.entity(entity);
+            assert!(entity.contains::<Pooled>() && !entity.contains::<Platform>());
+        }
+    }
+}
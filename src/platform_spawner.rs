@@ -1,82 +1,193 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::math::Rect;
 use bevy::prelude::*;
+use bevy_rapier2d::prelude::RapierConfiguration;
 use rand::Rng;
 
-use crate::{camera::MainCamera, platform::PlatformBundle};
+use crate::arena::FLOOR_RISE;
+use crate::camera::MainCamera;
+use crate::crumbling_platform::CrumblingPlatform;
+use crate::difficulty::DifficultyProfile;
+use crate::game_state::{GameState, RunEntity};
+use crate::giraffe::Giraffe;
+use crate::layout::{generate_layout, LayoutParams, PlatformSpec};
+use crate::moving_platform::{MovingPlatformBundle, PlatformPath, SpinningPlatformBundle};
+use crate::platform::{PlatformKind, PlatformSurface};
+use crate::platform_catalogue::{tallest_at_width, PlatformCatalogue};
+use crate::platform_pool::PlatformPool;
 
-const PLATFORM_SPAWN_DY: f32 = 200.0;
+/// How many of the highest platforms are remembered to check reachability of the next ones
+const RECENT_PLATFORMS: usize = 8;
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+const SPIN_ANGULAR_SPEED: f32 = 0.5;
 
 pub struct PlatformSpawnerPlugin;
 
+/// Seed of the platform layout, the same seed always gives the same platforms
+#[derive(Resource, Clone, Copy)]
+pub struct PlatformSeed(pub u64);
+
+impl PlatformSeed {
+    /// Reads the seed from `--seed <n>` or `--seed=<n>`
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        args.iter().enumerate().find_map(|(i, arg)| {
+            let value = match arg.strip_prefix("--seed") {
+                Some("") => args.get(i + 1)?.as_str(),
+                Some(value) => value.strip_prefix('=')?,
+                None => return None,
+            };
+            value.parse().ok().map(PlatformSeed)
+        })
+    }
+
+    pub fn random() -> Self {
+        PlatformSeed(rand::thread_rng().gen())
+    }
+
+    /// Seed shared by everyone playing on the same day
+    pub fn daily() -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        PlatformSeed(now.as_secs() / SECONDS_PER_DAY)
+    }
+}
+
 #[derive(Bundle)]
 struct PlatformSpawnerBundle {
     max_height: MaxHeightComponent,
+    recent: RecentPlatforms,
 }
 
 impl PlatformSpawnerBundle {
-    pub fn new() -> Self {
+    pub fn new(floor: PlatformSpec) -> Self {
         PlatformSpawnerBundle {
             max_height: MaxHeightComponent {
                 threshold: 2000.0,
-                prev_height: -2001.0,
+                prev_height: 0.0,
                 height: 0.0,
             },
+            recent: RecentPlatforms(vec![floor]),
         }
     }
 }
 
 #[derive(Component)]
-struct MaxHeightComponent {
+struct RecentPlatforms(Vec<PlatformSpec>);
+
+#[derive(Component)]
+pub struct MaxHeightComponent {
     threshold: f32,
+    /// Height up to which platforms are already spawned
     prev_height: f32,
-    height: f32,
+    /// Highest camera position so far
+    pub height: f32,
 }
 
-fn setup_platform_spawner(mut commands: Commands) {
-    commands.spawn(PlatformSpawnerBundle::new());
+fn setup_platform_spawner(mut commands: Commands, windows: Res<Windows>) {
+    let window = windows.get_primary().unwrap();
+    let floor = PlatformSpec {
+        pos: Vec2::new(0.0, -window.height() / 2.0),
+        size: Vec2::new(window.width(), FLOOR_RISE * 2.0),
+        kind: PlatformKind::Static,
+        surface: PlatformSurface::Normal,
+        variant: 0,
+        travel: Rect::default(),
+    };
+
+    commands.spawn((PlatformSpawnerBundle::new(floor), RunEntity));
 }
 
 fn generate_platforms(
     camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-    mut query: Query<&mut MaxHeightComponent>,
+    mut query: Query<(&mut MaxHeightComponent, &mut RecentPlatforms)>,
+    giraffe_query: Query<&Giraffe>,
     windows: Res<Windows>,
+    seed: Res<PlatformSeed>,
+    difficulty: Res<DifficultyProfile>,
+    catalogue: Res<PlatformCatalogue>,
+    mut pool: ResMut<PlatformPool>,
+    rapier_config: Res<RapierConfiguration>,
     mut commands: Commands,
 ) {
-    let mut maxheightcomponent;
-    if let Ok((_, glob_transform)) = camera_query.get_single() {
-        maxheightcomponent = query.single_mut();
+    if let (Ok((_, glob_transform)), Ok((mut maxheightcomponent, mut recent)), Ok(giraffe)) = (
+        camera_query.get_single(),
+        query.get_single_mut(),
+        giraffe_query.get_single(),
+    ) {
         maxheightcomponent.height =
             f32::max(glob_transform.translation().y, maxheightcomponent.height);
-        if maxheightcomponent.height - maxheightcomponent.threshold > maxheightcomponent.prev_height
+        if maxheightcomponent.height + maxheightcomponent.threshold > maxheightcomponent.prev_height
         {
             let window = windows.get_primary();
 
             let width = window.unwrap().width();
 
-            let mut rng = rand::thread_rng();
+            let band = difficulty.band_at(maxheightcomponent.height);
+            let params = LayoutParams {
+                width,
+                spacing: band.spacing,
+                spread: band.spread,
+                platform_size: Vec2::new(
+                    band.platform_width,
+                    tallest_at_width(band.platform_width),
+                ),
+                kinds: band.kinds.clone(),
+                surfaces: band.surfaces.clone(),
+            };
 
-            let mut i = PLATFORM_SPAWN_DY;
-            while i < maxheightcomponent.threshold {
-                let rx: f32 = (rng.gen::<f32>() * width) - width / 2.0;
-                // println!("{}, {}", rx, maxheightcomponent.prev_height + i);
+            let platforms = generate_layout(
+                seed.0,
+                maxheightcomponent.prev_height,
+                maxheightcomponent.prev_height + maxheightcomponent.threshold,
+                &recent.0,
+                &params,
+                &giraffe.reach(rapier_config.gravity.length()),
+            );
 
-                commands.spawn(PlatformBundle::type_one(
-                    Vec2 {
-                        x: rx,
-                        y: maxheightcomponent.height + i,
-                    },
-                    Vec2 { x: 300.0, y: 100.0 },
-                ));
+            for platform in platforms.iter() {
+                let entity = pool.spawn(
+                    &mut commands,
+                    catalogue
+                        .platform(platform.variant, platform.pos, platform.size.x)
+                        .with_surface(platform.surface),
+                );
 
-                i += PLATFORM_SPAWN_DY;
+                match platform.kind {
+                    PlatformKind::Crumbling => {
+                        commands.entity(entity).insert(CrumblingPlatform::new());
+                    }
+                    PlatformKind::Spinning => {
+                        commands
+                            .entity(entity)
+                            .insert(SpinningPlatformBundle::new(SPIN_ANGULAR_SPEED));
+                    }
+                    _ => {}
+                }
+                if let Some(path) = PlatformPath::for_kind(platform.kind, platform.pos) {
+                    commands.entity(entity).insert(MovingPlatformBundle::new(path));
+                }
             }
-            maxheightcomponent.height += maxheightcomponent.threshold;
+
+            recent.0.extend(platforms);
+            let excess = recent.0.len().saturating_sub(RECENT_PLATFORMS);
+            recent.0.drain(..excess);
+
+            maxheightcomponent.prev_height += maxheightcomponent.threshold;
         }
     }
 }
 
 impl Plugin for PlatformSpawnerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(generate_platforms)
-            .add_startup_system(setup_platform_spawner);
+        app.insert_resource(PlatformSeed::from_args().unwrap_or_else(PlatformSeed::random))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(setup_platform_spawner),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(generate_platforms),
+            );
     }
 }
@@ -0,0 +1,62 @@
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+
+pub struct RonAssetPlugin;
+
+/// Text of a `.ron` data file in the asset folder, parsed by whoever loaded it
+///
+/// Going through the asset server finds the file wherever the game is started from and
+/// reloads it when it changes on disk.
+#[derive(TypeUuid)]
+#[uuid = "3f6c2a9e-8d41-4b7a-a5e2-91c07d4b6f18"]
+pub struct RonSource(pub String);
+
+#[derive(Default)]
+struct RonSourceLoader;
+
+impl AssetLoader for RonSourceLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let source = String::from_utf8(bytes.to_vec())?;
+            load_context.set_default_asset(LoadedAsset::new(RonSource(source)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+/// Source of the file if it was just loaded or changed, the events are read either way
+pub fn updated_source<'a>(
+    events: &mut EventReader<AssetEvent<RonSource>>,
+    handle: &Handle<RonSource>,
+    sources: &'a Assets<RonSource>,
+) -> Option<&'a str> {
+    let updated = events.iter().fold(false, |updated, event| match event {
+        AssetEvent::Created { handle: changed } | AssetEvent::Modified { handle: changed } => {
+            updated || changed == handle
+        }
+        AssetEvent::Removed { .. } => updated,
+    });
+
+    if updated {
+        sources.get(handle).map(|source| source.0.as_str())
+    } else {
+        None
+    }
+}
+
+impl Plugin for RonAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<RonSource>()
+            .init_asset_loader::<RonSourceLoader>();
+    }
+}
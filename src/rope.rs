@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::game_state::GameState;
+use crate::neck::NeckPoints;
+
+/// Fraction of the rope stretch corrected each physics step
+const ROPE_BIAS: f32 = 0.2;
+
+#[derive(Component)]
+pub struct Rope {
+    /// Whole length of the neck, including the part wrapped around platforms
+    pub length: f32,
+    /// Velocity added only to pull the body back, taken away again after the step
+    correction: Vec2,
+}
+
+impl Rope {
+    pub fn new(length: f32) -> Self {
+        Self {
+            length,
+            correction: Vec2::ZERO,
+        }
+    }
+}
+
+pub struct RopePlugin;
+
+/// Runs after gameplay changed the velocities and before they are sent to the physics
+#[derive(StageLabel)]
+struct RopeConstraintStage;
+
+/// Runs once the physics wrote the step's results back
+#[derive(StageLabel)]
+struct RopeRelaxStage;
+
+/// Length of the physics step about to be simulated
+fn physics_dt(config: &RapierConfiguration, time: &Time) -> f32 {
+    match config.timestep_mode {
+        TimestepMode::Fixed { dt, .. } | TimestepMode::Interpolated { dt, .. } => dt,
+        TimestepMode::Variable {
+            max_dt, time_scale, ..
+        } => (time.delta_seconds() * time_scale).min(max_dt),
+    }
+}
+
+fn rope_constraint_system(
+    mut query: Query<(Entity, &Transform, &mut Velocity, &mut Rope)>,
+    neck_query: Query<&NeckPoints>,
+    rapier_config: Res<RapierConfiguration>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let dt = physics_dt(&rapier_config, &time);
+
+    for (entity, transform, mut velocity, mut rope) in query.iter_mut() {
+        if let Ok(neck) = neck_query.get_single() {
+            let anchor = neck.anchor();
+            let free_length = (rope.length - neck.wrapped_length()).max(0.0);
+
+            let offset = transform.translation.truncate() - anchor;
+            let distance = offset.length();
+            if distance <= free_length || distance == 0.0 {
+                continue;
+            }
+
+            let dir = offset / distance;
+
+            // Rope can only pull, so only the outward part of the velocity is removed
+            let radial_speed = velocity.linvel.dot(dir);
+            if radial_speed > 0.0 {
+                velocity.linvel -= dir * radial_speed;
+            }
+
+            if dt > 0.0 {
+                rope.correction = -dir * (distance - free_length) * ROPE_BIAS / dt;
+                velocity.linvel += rope.correction;
+            }
+        } else {
+            commands.entity(entity).remove::<Rope>();
+        }
+    }
+}
+
+/// Takes the stretch correction out of the velocity, so pulling the body back adds no energy
+fn remove_rope_correction(mut query: Query<(&mut Velocity, &mut Rope)>) {
+    for (mut velocity, mut rope) in query.iter_mut() {
+        velocity.linvel -= rope.correction;
+        rope.correction = Vec2::ZERO;
+    }
+}
+
+impl Plugin for RopePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_stage_before(
+            PhysicsStages::SyncBackend,
+            RopeConstraintStage,
+            SystemStage::parallel(),
+        )
+        .add_stage_after(
+            PhysicsStages::Writeback,
+            RopeRelaxStage,
+            SystemStage::parallel(),
+        )
+        .add_system_set_to_stage(
+            RopeConstraintStage,
+            SystemSet::on_update(GameState::Playing).with_system(rope_constraint_system),
+        )
+        .add_system_to_stage(RopeRelaxStage, remove_rope_correction);
+    }
+}
@@ -0,0 +1,77 @@
+use std::fs;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::game_state::GameState;
+use crate::giraffe::Giraffe;
+use crate::PIXELS_PER_METER;
+
+const BEST_HEIGHT_FILE: &str = "best_height.txt";
+
+pub struct ScorePlugin;
+
+#[derive(Resource, Default)]
+pub struct Score {
+    /// Highest altitude reached in this run, in meters
+    pub height: f32,
+    /// Best height over all runs, in meters
+    pub best: f32,
+}
+
+fn load_best_height() -> f32 {
+    fs::read_to_string(BEST_HEIGHT_FILE)
+        .ok()
+        .and_then(|best| best.trim().parse().ok())
+        .unwrap_or(0.0)
+}
+
+fn save_best_height(best: f32) {
+    if let Err(err) = fs::write(BEST_HEIGHT_FILE, best.to_string()) {
+        warn!("Couldn't save best height: {}", err);
+    }
+}
+
+fn update_score(query: Query<&Transform, With<Giraffe>>, mut score: ResMut<Score>) {
+    if let Ok(transform) = query.get_single() {
+        let height = (transform.translation.y / PIXELS_PER_METER).max(0.0);
+        if height > score.height {
+            score.height = height;
+        }
+        if score.height > score.best {
+            score.best = score.height;
+        }
+    }
+}
+
+fn reset_score(mut score: ResMut<Score>) {
+    score.height = 0.0;
+}
+
+/// Writes the best height when a run ends, runs are only left through game over or the menu
+fn persist_best_height(score: Res<Score>) {
+    save_best_height(score.best);
+}
+
+fn persist_best_height_on_exit(mut exit_events: EventReader<AppExit>, score: Res<Score>) {
+    if exit_events.iter().next().is_some() {
+        save_best_height(score.best);
+    }
+}
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        let best = load_best_height();
+        app.insert_resource(Score { height: 0.0, best })
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_score))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(update_score))
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(persist_best_height),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::MainMenu).with_system(persist_best_height),
+            )
+            // Closing the window sends the exit event late in the frame
+            .add_system_to_stage(CoreStage::Last, persist_best_height_on_exit);
+    }
+}
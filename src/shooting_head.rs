@@ -1,37 +1,221 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-const FLY_SPEED: f32 = 10.0;
+use crate::game_state::GameState;
+use crate::head::Head;
+use crate::neck::{NeckHold, NeckPoints, NECK_GROUP};
+use crate::platform::{Platform, PlatformSurface, PLATFORM_GROUP};
+
+const FLY_SPEED: f32 = 1500.0;
+const MAX_RANGE: f32 = 1500.0;
+pub const HEAD_RADIUS: f32 = 25.0;
+
+pub struct ShootingHeadPlugin;
+
+/// Sent when the flying head grabs a platform
+pub struct NeckAttachEvent {
+    pub point: Vec2,
+}
+
+#[derive(PartialEq, Eq)]
+enum ShootingHeadState {
+    Flying,
+    Attached,
+    Retracting,
+}
 
 #[derive(Component)]
-pub struct ShootingHead(Vec2);
+pub struct ShootingHead {
+    velocity: Vec2,
+    state: ShootingHeadState,
+    neck: Entity,
+    max_range: f32,
+}
 
 #[derive(Bundle)]
 pub struct ShootingHeadBundle {
     head: ShootingHead,
     sprite: SpriteBundle,
-    // collider: Collider,
-}
-
-pub fn ShootingHeadSystem(mut query: Query<(&mut Transform, &ShootingHead)>) {
-    for (mut transform, vel) in query.iter_mut() {
-        transform.translation += vel.0.extend(0.0);
-    }
+    collider: Collider,
+    sensor: Sensor,
+    groups: CollisionGroups,
+    collision_types: ActiveCollisionTypes,
 }
 
 impl ShootingHeadBundle {
-    pub fn new(transform: Transform, velocity: Vec2) -> Self {
+    pub fn new(transform: Transform, direction: Vec2, neck: Entity) -> Self {
         ShootingHeadBundle {
-            head: ShootingHead(velocity * FLY_SPEED),
+            head: ShootingHead {
+                velocity: direction.normalize_or_zero() * FLY_SPEED,
+                state: ShootingHeadState::Flying,
+                neck,
+                max_range: MAX_RANGE,
+            },
             sprite: SpriteBundle {
                 sprite: Sprite {
-                    color: Color::RED,
-                    custom_size: Some(Vec2 { x: 25., y: 25. }),
+                    custom_size: Some(Vec2 { x: 80., y: 80. }),
                     ..default()
                 },
-                transform: transform,
+                transform,
                 ..default()
             },
+            collider: Collider::ball(HEAD_RADIUS),
+            sensor: Sensor,
+            groups: CollisionGroups::new(
+                Group::from_bits(NECK_GROUP.bits()).unwrap(),
+                Group::from_bits(PLATFORM_GROUP.bits()).unwrap(),
+            ),
+            // The head and most platforms have no rigid body, moving platforms are kinematic
+            collision_types: ActiveCollisionTypes::default()
+                | ActiveCollisionTypes::STATIC_STATIC
+                | ActiveCollisionTypes::KINEMATIC_STATIC,
+        }
+    }
+
+    pub fn with_max_range(mut self, max_range: f32) -> Self {
+        self.head.max_range = max_range;
+        self
+    }
+
+    pub fn with_texture(mut self, texture: Handle<Image>) -> Self {
+        self.sprite.texture = texture;
+        self
+    }
+}
+
+type PlatformQuery<'w, 's> =
+    Query<'w, 's, (&'static GlobalTransform, Option<&'static PlatformSurface>), With<Platform>>;
+
+fn hit_platform(
+    entity: Entity,
+    transform: &Transform,
+    velocity: Vec2,
+    platforms: &PlatformQuery,
+    rapier_ctx: &RapierContext,
+) -> Option<(Entity, Vec2)> {
+    for (collider1, collider2, intersecting) in rapier_ctx.intersections_with(entity) {
+        let other = if collider1 == entity {
+            collider2
+        } else {
+            collider1
+        };
+
+        if !intersecting || !platforms.contains(other) {
+            continue;
+        }
+
+        // Sensor only tells what was hit, the ray finds where the head touched it
+        let dir = velocity.normalize();
+        let ray_start = transform.translation.truncate() - dir * HEAD_RADIUS * 2.0;
+        let filter = |e: Entity| e == other;
+        let point = rapier_ctx
+            .cast_ray(
+                ray_start,
+                dir,
+                HEAD_RADIUS * 4.0,
+                false,
+                QueryFilter::new().predicate(&filter),
+            )
+            .map(|(_, toi)| ray_start + dir * toi)
+            .unwrap_or(transform.translation.truncate());
+
+        return Some((other, point));
+    }
+    None
+}
+
+fn shooting_head_system(
+    mut query: Query<(Entity, &mut Transform, &mut ShootingHead)>,
+    mut neck_query: Query<&mut NeckPoints>,
+    mut head_query: Query<(&GlobalTransform, &mut Visibility), With<Head>>,
+    platforms: PlatformQuery,
+    rapier_ctx: Res<RapierContext>,
+    time: Res<Time>,
+    mut attach_events: EventWriter<NeckAttachEvent>,
+    mut commands: Commands,
+) {
+    for (entity, mut transform, mut head) in query.iter_mut() {
+        let mut neck = if let Ok(neck) = neck_query.get_mut(head.neck) {
+            neck
+        } else {
+            // Neck was removed, so the head goes back on the giraffe
+            commands.entity(entity).despawn();
+            if let Ok((_, mut visibility)) = head_query.get_single_mut() {
+                visibility.is_visible = true;
+            }
+            continue;
+        };
+
+        let step = FLY_SPEED * time.delta_seconds();
+
+        match head.state {
+            ShootingHeadState::Flying => {
+                let hit = hit_platform(entity, &transform, head.velocity, &platforms, &rapier_ctx);
+                let can_anchor = |platform: Entity| match platforms.get(platform) {
+                    Ok((_, surface)) => surface.map_or(true, PlatformSurface::can_anchor),
+                    Err(_) => false,
+                };
+
+                if let Some((platform, point)) = hit.filter(|(platform, _)| can_anchor(*platform)) {
+                    head.state = ShootingHeadState::Attached;
+                    transform.translation = point.extend(transform.translation.z);
+
+                    let hold = platforms.get(platform).ok().map(|(platform_transform, _)| {
+                        NeckHold {
+                            entity: platform,
+                            local: platform_transform
+                                .affine()
+                                .inverse()
+                                .transform_point3(point.extend(0.0))
+                                .truncate(),
+                        }
+                    });
+                    neck.reset(point, hold);
+
+                    attach_events.send(NeckAttachEvent { point });
+                } else if hit.is_some() {
+                    // Slid off a surface the neck can't hold on to
+                    head.state = ShootingHeadState::Retracting;
+                } else {
+                    transform.translation += (head.velocity * time.delta_seconds()).extend(0.0);
+
+                    if transform.translation.truncate().distance(neck.last_point) > head.max_range {
+                        head.state = ShootingHeadState::Retracting;
+                    }
+                }
+            }
+            ShootingHeadState::Attached => {
+                // Stay on the grabbed point, which moves together with its platform
+                transform.translation = neck.points[0].extend(transform.translation.z);
+            }
+            ShootingHeadState::Retracting => {
+                if let Ok((head_transform, _)) = head_query.get_single() {
+                    let to_head = head_transform.translation().truncate()
+                        - transform.translation.truncate();
+
+                    if to_head.length() <= step {
+                        commands.entity(head.neck).despawn();
+                        continue;
+                    }
+
+                    head.velocity = to_head.normalize() * FLY_SPEED;
+                    transform.translation += (head.velocity * time.delta_seconds()).extend(0.0);
+                }
+            }
         }
+
+        // Until attached the neck is a straight line between the body and the flying head
+        if head.state != ShootingHeadState::Attached {
+            neck.reset(transform.translation.truncate(), None);
+        }
+    }
+}
+
+impl Plugin for ShootingHeadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<NeckAttachEvent>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(shooting_head_system),
+            );
     }
 }